@@ -1,26 +1,31 @@
 use baltree::avl_tree::AVLTree;
 use baltree::rb_tree::RedBlackTree;
+use baltree::{ArenaBackend, OrderedSet, RcBackend};
 use criterion::{criterion_group, criterion_main, Criterion};
 
-fn benchmark_avl_tree(c: &mut Criterion) {
+fn benchmark_tree<T: OrderedSet<i32> + Default + FromIterator<i32>>(c: &mut Criterion, name: &str) {
     let tree_sizes = [10_000, 40_000, 70_000, 100_000, 130_000];
 
     for &size in &tree_sizes {
-        let mut group = c.benchmark_group(format!("AVL Tree Size {}", size));
+        let mut group = c.benchmark_group(format!("{} Tree Size {}", name, size));
 
-        group.bench_function("AVL Insert", |b| {
+        group.bench_function(format!("{} Insert", name), |b| {
             b.iter(|| {
-                let mut tree = AVLTree::new();
+                let mut tree = T::default();
                 for value in 1..=size {
                     tree.insert(value);
                 }
             });
         });
 
-        group.bench_with_input("AVL Search", &size, |b, &size| {
+        group.bench_function(format!("{} Bulk Build", name), |b| {
+            b.iter(|| T::from_iter(1..=size));
+        });
+
+        group.bench_with_input(format!("{} Search", name), &size, |b, &size| {
             b.iter_batched_ref(
                 || {
-                    let mut tree = AVLTree::new();
+                    let mut tree = T::default();
                     for value in 1..=size {
                         tree.insert(value);
                     }
@@ -28,7 +33,7 @@ fn benchmark_avl_tree(c: &mut Criterion) {
                 },
                 |tree| {
                     for value in 1..=(size / 10) {
-                        tree.tree.search(value);
+                        tree.contains(&value);
                     }
                 },
                 criterion::BatchSize::SmallInput,
@@ -39,41 +44,65 @@ fn benchmark_avl_tree(c: &mut Criterion) {
     }
 }
 
-fn benchmark_rb_tree(c: &mut Criterion) {
-    let tree_sizes = [10_000, 40_000, 70_000, 100_000, 130_000];
+// Splits a tree in half and appends the upper half back. With Rc storage both steps take
+// logarithmic time. With arena storage the half that moves is copied between arenas, so the
+// time grows with the size of the tree.
+fn benchmark_split_append<T: OrderedSet<i32> + Default>(
+    c: &mut Criterion,
+    name: &str,
+    split_off: fn(&mut T, &i32) -> T,
+    append: fn(&mut T, &mut T),
+) {
+    let tree_sizes = [10_000, 100_000, 1_000_000];
 
+    let mut group = c.benchmark_group(format!("{} Split And Append", name));
     for &size in &tree_sizes {
-        let mut group = c.benchmark_group(format!("RB Tree Size {}", size));
-
-        group.bench_function("RB Insert", |b| {
+        let mut tree = T::default();
+        for value in 1..=size {
+            tree.insert(value);
+        }
+        group.bench_function(format!("{} Split And Append {}", name, size), |b| {
             b.iter(|| {
-                let mut tree = RedBlackTree::new();
-                for value in 1..=size {
-                    tree.insert(value);
-                }
+                let mut upper = split_off(&mut tree, &(size / 2));
+                append(&mut tree, &mut upper);
             });
         });
+    }
+    group.finish();
+}
 
-        group.bench_with_input("RB Search", &size, |b, &size| {
-            b.iter_batched_ref(
-                || {
-                    let mut tree = RedBlackTree::new();
-                    for value in 1..=size {
-                        tree.insert(value);
-                    }
-                    tree
-                },
-                |tree| {
-                    for value in 1..=(size / 10) {
-                        tree.tree.search(value);
-                    }
-                },
-                criterion::BatchSize::SmallInput,
-            );
-        });
+fn benchmark_avl_tree(c: &mut Criterion) {
+    benchmark_tree::<AVLTree<i32, RcBackend>>(c, "AVL");
+    benchmark_tree::<AVLTree<i32, ArenaBackend>>(c, "AVL Arena");
+    benchmark_split_append::<AVLTree<i32, RcBackend>>(
+        c,
+        "AVL",
+        AVLTree::split_off,
+        AVLTree::append,
+    );
+    benchmark_split_append::<AVLTree<i32, ArenaBackend>>(
+        c,
+        "AVL Arena",
+        AVLTree::split_off,
+        AVLTree::append,
+    );
+}
 
-        group.finish();
-    }
+fn benchmark_rb_tree(c: &mut Criterion) {
+    benchmark_tree::<RedBlackTree<i32, RcBackend>>(c, "RB");
+    benchmark_tree::<RedBlackTree<i32, ArenaBackend>>(c, "RB Arena");
+    benchmark_split_append::<RedBlackTree<i32, RcBackend>>(
+        c,
+        "RB",
+        RedBlackTree::split_off,
+        RedBlackTree::append,
+    );
+    benchmark_split_append::<RedBlackTree<i32, ArenaBackend>>(
+        c,
+        "RB Arena",
+        RedBlackTree::split_off,
+        RedBlackTree::append,
+    );
 }
 
 criterion_group!(benches, benchmark_avl_tree, benchmark_rb_tree);
@@ -0,0 +1,207 @@
+use crate::common_tree::NodeColor;
+use crate::storage::{Backend, Sealed, Storage};
+use std::mem;
+
+// Marks a missing child or parent, and the end of the free list
+const NIL: u32 = u32::MAX;
+
+/// Stores the nodes of a tree in one contiguous `Vec`, linked by `u32` indices instead of
+/// reference-counted pointers. Slots of deleted nodes are recycled through a free list.
+pub struct ArenaBackend;
+
+impl Backend for ArenaBackend {}
+
+impl Sealed for ArenaBackend {
+    type Store<K, V> = ArenaStore<K, V>;
+}
+
+pub struct ArenaStore<K, V> {
+    slots: Vec<Slot<K, V>>,
+    free_head: u32,
+}
+
+enum Slot<K, V> {
+    Occupied(ArenaNode<K, V>),
+    // Holds the index of the next free slot
+    Vacant(u32),
+}
+
+struct ArenaNode<K, V> {
+    key: K,
+    value: V,
+    left: u32,
+    right: u32,
+    parent: u32,
+    height: i32,
+    // Fits in 32 bits, as the arena never holds more nodes than it has indices
+    size: u32,
+    color: Option<NodeColor>,
+}
+
+impl<K, V> Default for ArenaStore<K, V> {
+    fn default() -> Self {
+        ArenaStore {
+            slots: Vec::new(),
+            free_head: NIL,
+        }
+    }
+}
+
+fn to_link(index: u32) -> Option<u32> {
+    (index != NIL).then_some(index)
+}
+
+fn to_index(link: Option<u32>) -> u32 {
+    link.unwrap_or(NIL)
+}
+
+impl<K, V> ArenaStore<K, V> {
+    fn node(&self, index: u32) -> &ArenaNode<K, V> {
+        match &self.slots[index as usize] {
+            Slot::Occupied(node) => node,
+            Slot::Vacant(_) => panic!("Node {} has been freed", index),
+        }
+    }
+
+    fn node_mut(&mut self, index: u32) -> &mut ArenaNode<K, V> {
+        match &mut self.slots[index as usize] {
+            Slot::Occupied(node) => node,
+            Slot::Vacant(_) => panic!("Node {} has been freed", index),
+        }
+    }
+
+    // Stores a node in a free slot, reusing the most recently freed one
+    fn place(&mut self, node: ArenaNode<K, V>) -> u32 {
+        let node = Slot::Occupied(node);
+        if self.free_head != NIL {
+            let index = self.free_head;
+            match mem::replace(&mut self.slots[index as usize], node) {
+                Slot::Vacant(next) => self.free_head = next,
+                Slot::Occupied(_) => unreachable!("Free list points at a live node"),
+            }
+            index
+        } else {
+            let index = u32::try_from(self.slots.len())
+                .ok()
+                .filter(|&index| index != NIL)
+                .expect("Arena is full");
+            self.slots.push(node);
+            index
+        }
+    }
+
+    // Takes a node out of its slot and puts the slot on the free list
+    fn release(&mut self, index: u32) -> ArenaNode<K, V> {
+        let slot = mem::replace(
+            &mut self.slots[index as usize],
+            Slot::Vacant(self.free_head),
+        );
+        match slot {
+            Slot::Occupied(node) => {
+                self.free_head = index;
+                node
+            }
+            Slot::Vacant(_) => panic!("Node {} has been freed", index),
+        }
+    }
+}
+
+impl<K, V> Storage<K, V> for ArenaStore<K, V> {
+    type Link = u32;
+
+    fn alloc(&mut self, key: K, value: V) -> u32 {
+        self.place(ArenaNode {
+            key,
+            value,
+            left: NIL,
+            right: NIL,
+            parent: NIL,
+            height: 1,
+            size: 1,
+            color: None,
+        })
+    }
+
+    unsafe fn free(&mut self, node: u32) -> (K, V) {
+        let node = self.release(node);
+        (node.key, node.value)
+    }
+
+    unsafe fn adopt(&mut self, other: &mut Self, node: u32) -> u32 {
+        let moved = other.release(node);
+        let (left, right) = (moved.left, moved.right);
+        let index = self.place(moved);
+        self.node_mut(index).parent = NIL;
+        if left != NIL {
+            let left = unsafe { self.adopt(other, left) };
+            self.node_mut(left).parent = index;
+            self.node_mut(index).left = left;
+        }
+        if right != NIL {
+            let right = unsafe { self.adopt(other, right) };
+            self.node_mut(right).parent = index;
+            self.node_mut(index).right = right;
+        }
+        index
+    }
+
+    unsafe fn left(&self, node: &u32) -> Option<u32> {
+        to_link(self.node(*node).left)
+    }
+
+    unsafe fn right(&self, node: &u32) -> Option<u32> {
+        to_link(self.node(*node).right)
+    }
+
+    unsafe fn parent(&self, node: &u32) -> Option<u32> {
+        to_link(self.node(*node).parent)
+    }
+
+    unsafe fn set_left(&mut self, node: &u32, child: Option<u32>) {
+        self.node_mut(*node).left = to_index(child);
+    }
+
+    unsafe fn set_right(&mut self, node: &u32, child: Option<u32>) {
+        self.node_mut(*node).right = to_index(child);
+    }
+
+    unsafe fn set_parent(&mut self, node: &u32, parent: Option<u32>) {
+        self.node_mut(*node).parent = to_index(parent);
+    }
+
+    unsafe fn height(&self, node: &u32) -> i32 {
+        self.node(*node).height
+    }
+
+    unsafe fn set_height(&mut self, node: &u32, height: i32) {
+        self.node_mut(*node).height = height;
+    }
+
+    unsafe fn size(&self, node: &u32) -> usize {
+        self.node(*node).size as usize
+    }
+
+    unsafe fn set_size(&mut self, node: &u32, size: usize) {
+        self.node_mut(*node).size = size as u32;
+    }
+
+    unsafe fn color(&self, node: &u32) -> Option<NodeColor> {
+        self.node(*node).color.clone()
+    }
+
+    unsafe fn set_color(&mut self, node: &u32, color: Option<NodeColor>) {
+        self.node_mut(*node).color = color;
+    }
+
+    unsafe fn key(&self, node: &u32) -> &K {
+        &self.node(*node).key
+    }
+
+    unsafe fn value(&self, node: &u32) -> &V {
+        &self.node(*node).value
+    }
+
+    unsafe fn value_mut(&mut self, node: &u32) -> &mut V {
+        &mut self.node_mut(*node).value
+    }
+}
@@ -0,0 +1,127 @@
+use crate::common_tree::CommonTree;
+use crate::entry::{Attach, Path};
+use crate::iter::{after_start, before_end};
+use crate::storage::{Backend, Link};
+use std::cmp::Ordering;
+use std::ops::{Bound, RangeBounds};
+
+/// A summary of the entries in a subtree, which every node of an augmented tree keeps for its
+/// own subtree and recomputes from its children whenever the tree is rebalanced below it.
+///
+/// The summaries have to form a monoid: `combine` is associative and combining anything with
+/// `identity` leaves it unchanged. Sums, minimums, maximums and counts all qualify.
+pub trait Augment<K, V> {
+    type Summary: Clone;
+
+    /// The summary of no entries at all.
+    fn identity() -> Self::Summary;
+
+    /// The summary of a single entry.
+    fn measure(key: &K, value: &V) -> Self::Summary;
+
+    /// The summary of the entries of `left` followed by the entries of `right`.
+    fn combine(left: &Self::Summary, right: &Self::Summary) -> Self::Summary;
+}
+
+// What each node of an augmented tree holds: its own value and the summary of its subtree
+pub(crate) type Summarized<K, V, A> = (V, <A as Augment<K, V>>::Summary);
+
+// An empty tree that keeps the summaries of `A` up to date
+pub(crate) fn tree<K: Ord, V, A: Augment<K, V>, B: Backend>(
+) -> CommonTree<K, Summarized<K, V, A>, B> {
+    CommonTree {
+        refresh: Some(refresh::<K, V, A, B>),
+        ..CommonTree::default()
+    }
+}
+
+fn refresh<K, V, A: Augment<K, V>, B: Backend>(
+    tree: &mut CommonTree<K, Summarized<K, V, A>, B>,
+    node: &Link<K, Summarized<K, V, A>, B>,
+) {
+    let own = A::measure(tree.key(node), &tree.value(node).0);
+    let left = summary::<K, V, A, B>(tree, &tree.left(node));
+    let right = summary::<K, V, A, B>(tree, &tree.right(node));
+    tree.value_mut(node).1 = A::combine(&A::combine(&left, &own), &right);
+}
+
+pub(crate) fn summary<K, V, A: Augment<K, V>, B: Backend>(
+    tree: &CommonTree<K, Summarized<K, V, A>, B>,
+    node: &Option<Link<K, Summarized<K, V, A>, B>>,
+) -> A::Summary {
+    node.as_ref()
+        .map_or_else(A::identity, |node| tree.value(node).1.clone())
+}
+
+// Inserts or replaces the value of `key`, returning the value it replaces
+pub(crate) fn insert<K: Ord, V, A: Augment<K, V>, B: Backend>(
+    tree: &mut CommonTree<K, Summarized<K, V, A>, B>,
+    key: K,
+    value: V,
+    attach: Attach<K, Summarized<K, V, A>, B>,
+) -> Option<V> {
+    // A new value changes the summary of every subtree that holds it, so the nodes down to the
+    // key are kept to update them afterwards. A new key is attached at the end of the same path.
+    let mut path: Path<K, Summarized<K, V, A>, B> = Vec::new();
+    let mut current = tree.root.clone();
+    while let Some(node) = current {
+        let ordering = key.cmp(tree.key(&node));
+        current = match ordering {
+            Ordering::Less => tree.left(&node),
+            Ordering::Greater => tree.right(&node),
+            Ordering::Equal => {
+                let old = std::mem::replace(&mut tree.value_mut(&node).0, value);
+                tree.update_size(&node);
+                for (node, _) in path.iter().rev() {
+                    tree.update_size(node);
+                }
+                return Some(old);
+            }
+        };
+        path.push((node, ordering));
+    }
+    let summary = A::measure(&key, &value);
+    let node = tree.new_node(key, (value, summary));
+    attach(tree, path, node);
+    None
+}
+
+// Combines the summaries of the keys between `start` and `end`, using whole subtrees wherever
+// the range covers them, so only the two paths to the ends of the range are walked
+fn fold_between<K: Ord, V, A: Augment<K, V>, B: Backend>(
+    tree: &CommonTree<K, Summarized<K, V, A>, B>,
+    node: Option<Link<K, Summarized<K, V, A>, B>>,
+    start: Bound<&K>,
+    end: Bound<&K>,
+) -> A::Summary {
+    let Some(node) = node else {
+        return A::identity();
+    };
+    if matches!((start, end), (Bound::Unbounded, Bound::Unbounded)) {
+        return tree.value(&node).1.clone();
+    }
+    let key = tree.key(&node);
+    if !after_start(start, key) {
+        return fold_between::<K, V, A, B>(tree, tree.right(&node), start, end);
+    }
+    if !before_end(end, key) {
+        return fold_between::<K, V, A, B>(tree, tree.left(&node), start, end);
+    }
+    // The range holds this key, so it reaches past the far end of one child on each side
+    let left = fold_between::<K, V, A, B>(tree, tree.left(&node), start, Bound::Unbounded);
+    let right = fold_between::<K, V, A, B>(tree, tree.right(&node), Bound::Unbounded, end);
+    let own = A::measure(key, &tree.value(&node).0);
+    A::combine(&A::combine(&left, &own), &right)
+}
+
+pub(crate) fn fold_range<K: Ord, V, A: Augment<K, V>, B: Backend, R: RangeBounds<K>>(
+    tree: &CommonTree<K, Summarized<K, V, A>, B>,
+    range: R,
+) -> A::Summary {
+    fold_between::<K, V, A, B>(
+        tree,
+        tree.root.clone(),
+        range.start_bound(),
+        range.end_bound(),
+    )
+}
@@ -1,181 +1,1178 @@
-use crate::common_tree::{CommonTree, GenericTree, Node, Tree};
-use std::cell::RefCell;
-use std::rc::Rc;
+use crate::augment::{self, Augment, Summarized};
+use crate::common_tree::CommonTree;
+use crate::cursor::{Cursor, CursorMut};
+use crate::entry::{Entry, Path};
+use crate::error::{Invariant, InvariantViolation, TreeError};
+use crate::iter::{IntoIter, Iter, MultiIntoIter, MultiIter, Range};
+use crate::node::NodeRef;
+use crate::ordered_set::OrderedSet;
+use crate::rb_tree::RedBlackTree;
+use crate::set_ops::{self, Difference, Intersection, Join, Operation, SymmetricDifference, Union};
+use crate::storage::{Backend, Link, RcBackend};
+use std::cmp::Ordering;
+use std::fmt::Display;
+use std::ops::{Bound, RangeBounds};
 
-pub struct AVLTree {
-    pub tree: CommonTree,
+// A split subtree: the keys before the split key, the node holding it if there is one, and the
+// keys after it
+type Split<K, V, B> = (
+    Option<Link<K, V, B>>,
+    Option<Link<K, V, B>>,
+    Option<Link<K, V, B>>,
+);
+
+pub struct AVLTree<K, B: Backend = RcBackend> {
+    pub(crate) tree: CommonTree<K, (), B>,
 }
 
-impl AVLTree {
-    pub fn new() -> Self {
+/// An ordered map backed by the same rebalancing code as [`AVLTree`].
+pub struct AVLMap<K, V, B: Backend = RcBackend> {
+    tree: CommonTree<K, V, B>,
+}
+
+/// An ordered map backed by the same rebalancing code as [`AVLTree`], where every node also
+/// keeps the [`Augment`] summary of its subtree, so that any range of keys can be summarized in
+/// O(log n). Values are only changed through `insert`, which keeps the summaries up to date.
+pub struct AVLAugmentedMap<K, V, A: Augment<K, V>, B: Backend = RcBackend> {
+    tree: CommonTree<K, Summarized<K, V, A>, B>,
+}
+
+/// An ordered multiset backed by the same rebalancing code as [`AVLTree`]. Each distinct key
+/// has one node, which counts how many copies of the key were inserted.
+pub struct AVLMultiset<K, B: Backend = RcBackend> {
+    tree: CommonTree<K, usize, B>,
+    // Copies of all keys together, where the tree itself counts distinct keys
+    len: usize,
+}
+
+impl<K: Ord, B: Backend> Default for AVLTree<K, B> {
+    fn default() -> Self {
         AVLTree {
-            tree: CommonTree::new(),
-        }
-    }
-
-    pub fn insert(&mut self, key: i32) {
-        let new_node = Rc::new(RefCell::new(Node {
-            key,
-            left: None,
-            right: None,
-            parent: None,
-            height: 1,
-            color: None, // Default color for AVLTree nodes
-        }));
-        self.tree.root = Some(AVLTree::insert_node(self.tree.root.clone(), new_node));
-    }
-
-    fn insert_node(root: GenericTree, new_node: Tree) -> Tree {
-        match root {
-            Some(node) => {
-                {
-                    let temp_left = node.borrow().left.clone();
-                    let temp_right = node.borrow().right.clone();
-
-                    if new_node.borrow().key < node.borrow().key {
-                        let left_tree = AVLTree::insert_node(temp_left, new_node.clone());
-                        node.borrow_mut().left = Some(left_tree);
-                    } else {
-                        let right_tree = AVLTree::insert_node(temp_right, new_node.clone());
-                        node.borrow_mut().right = Some(right_tree);
-                    }
-                }
-                AVLTree::balance(node)
+            tree: CommonTree::default(),
+        }
+    }
+}
+
+impl<K: Ord> AVLTree<K> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<K: Ord, B: Backend> AVLTree<K, B> {
+    /// Builds a perfectly balanced tree from keys in strictly ascending order, in O(n).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TreeError::NotAscending`] if a key is not larger than the one before it.
+    pub fn from_sorted_iter<I: IntoIterator<Item = K>>(iter: I) -> Result<Self, TreeError> {
+        let mut tree = CommonTree::default();
+        tree.build_sorted(iter.into_iter().map(|key| (key, ())), false)?;
+        Ok(AVLTree { tree })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    pub fn contains(&self, key: &K) -> bool {
+        self.tree.search(key).is_some()
+    }
+
+    /// Returns the number of nodes on the longest path from the root down to a leaf.
+    pub fn height(&self) -> i32 {
+        self.tree.get_height()
+    }
+
+    /// Iterates over the keys in ascending order.
+    pub fn iter(&self) -> Iter<'_, K, (), B> {
+        self.tree.iter()
+    }
+
+    /// Iterates in ascending order over the keys that fall within `range`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start of the range lies after its end.
+    pub fn range<R: RangeBounds<K>>(&self, range: R) -> Range<'_, K, (), B> {
+        self.tree.range(range)
+    }
+
+    /// Returns the key at position `index` in ascending order.
+    pub fn select(&self, index: usize) -> Option<K>
+    where
+        K: Clone,
+    {
+        self.tree.select(index)
+    }
+
+    /// Returns the number of keys that are smaller than `key`.
+    pub fn rank(&self, key: &K) -> usize {
+        self.tree.rank(key)
+    }
+
+    /// Returns the smallest key.
+    pub fn first(&self) -> Option<K>
+    where
+        K: Clone,
+    {
+        self.tree.min()
+    }
+
+    /// Returns the largest key.
+    pub fn last(&self) -> Option<K>
+    where
+        K: Clone,
+    {
+        self.tree.max()
+    }
+
+    /// Returns the largest key that is smaller than or equal to `key`.
+    pub fn floor(&self, key: &K) -> Option<K>
+    where
+        K: Clone,
+    {
+        self.tree.floor(key)
+    }
+
+    /// Returns the smallest key that is larger than or equal to `key`.
+    pub fn ceiling(&self, key: &K) -> Option<K>
+    where
+        K: Clone,
+    {
+        self.tree.ceiling(key)
+    }
+
+    /// Returns the largest key that is strictly smaller than `key`.
+    pub fn predecessor(&self, key: &K) -> Option<K>
+    where
+        K: Clone,
+    {
+        self.tree.predecessor(key)
+    }
+
+    /// Returns the smallest key that is strictly larger than `key`.
+    pub fn successor(&self, key: &K) -> Option<K>
+    where
+        K: Clone,
+    {
+        self.tree.successor(key)
+    }
+
+    /// Inserts `key` unless it is already present, and returns whether it was inserted. See
+    /// [`AVLMultiset`] for counting duplicates instead.
+    pub fn insert(&mut self, key: K) -> bool {
+        match self.entry(key) {
+            Entry::Vacant(entry) => {
+                entry.insert(());
+                true
+            }
+            Entry::Occupied(_) => false,
+        }
+    }
+
+    /// Looks up `key` once, for inserting it or reaching it without another search.
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, (), B> {
+        Entry::new(&mut self.tree, key, Self::attach)
+    }
+
+    /// Returns a cursor at the smallest key, or past the ends if the tree is empty.
+    pub fn cursor_front(&self) -> Cursor<'_, K, (), B> {
+        Cursor::new(&self.tree, self.tree.first_node())
+    }
+
+    /// Returns a cursor at the largest key, or past the ends if the tree is empty.
+    pub fn cursor_back(&self) -> Cursor<'_, K, (), B> {
+        Cursor::new(&self.tree, self.tree.last_node())
+    }
+
+    /// Returns a cursor at the smallest key within `bound`, or past the ends if there is none.
+    pub fn lower_bound(&self, bound: Bound<&K>) -> Cursor<'_, K, (), B> {
+        Cursor::new(&self.tree, self.tree.first_node_after(bound))
+    }
+
+    pub fn cursor_front_mut(&mut self) -> CursorMut<'_, K, (), B> {
+        let first = self.tree.first_node();
+        CursorMut::new(&mut self.tree, first, Self::attach, Self::delete_node)
+    }
+
+    pub fn cursor_back_mut(&mut self) -> CursorMut<'_, K, (), B> {
+        let last = self.tree.last_node();
+        CursorMut::new(&mut self.tree, last, Self::attach, Self::delete_node)
+    }
+
+    pub fn lower_bound_mut(&mut self, bound: Bound<&K>) -> CursorMut<'_, K, (), B> {
+        let node = self.tree.first_node_after(bound);
+        CursorMut::new(&mut self.tree, node, Self::attach, Self::delete_node)
+    }
+
+    /// Returns a read-only view of the root, from which the shape of the tree can be walked,
+    /// or `None` if the tree is empty.
+    pub fn root(&self) -> Option<NodeRef<'_, K, (), B>> {
+        NodeRef::root(&self.tree)
+    }
+
+    // Links `node` below the end of `path` and rebalances every node on the way back up
+    fn attach<V>(tree: &mut CommonTree<K, V, B>, path: Path<K, V, B>, node: Link<K, V, B>) {
+        let mut child = node;
+        for (parent, ordering) in path.into_iter().rev() {
+            if ordering == Ordering::Less {
+                tree.link_left(&parent, Some(child));
+            } else {
+                tree.link_right(&parent, Some(child));
             }
-            None => new_node,
+            child = Self::balance(tree, parent);
         }
+        tree.set_root(Some(child));
+        tree.len += 1;
     }
 
-    fn balance(node: Tree) -> Tree {
-        CommonTree::update_height(&node);
-        let diff = CommonTree::get_balance(&node);
+    fn balance<V>(tree: &mut CommonTree<K, V, B>, node: Link<K, V, B>) -> Link<K, V, B> {
+        tree.update_size(&node);
+        let diff = tree.get_balance(&node);
         if diff > 1 {
-            if CommonTree::get_balance(&node.borrow().left.as_ref().unwrap()) < 0 {
-                let left = node.borrow_mut().left.take().unwrap();
-                node.borrow_mut().left = Some(AVLTree::rotate_left(left));
+            let left = tree.left(&node).unwrap();
+            if tree.get_balance(&left) < 0 {
+                let left = AVLTree::rotate_left(tree, left);
+                tree.link_left(&node, Some(left));
             }
-            return AVLTree::rotate_right(node);
+            return AVLTree::rotate_right(tree, node);
         } else if diff < -1 {
-            if CommonTree::get_balance(&node.borrow().right.as_ref().unwrap()) > 0 {
-                let right = node.borrow_mut().right.take().unwrap();
-                node.borrow_mut().right = Some(AVLTree::rotate_right(right));
+            let right = tree.right(&node).unwrap();
+            if tree.get_balance(&right) > 0 {
+                let right = AVLTree::rotate_right(tree, right);
+                tree.link_right(&node, Some(right));
             }
-            return AVLTree::rotate_left(node);
+            return AVLTree::rotate_left(tree, node);
         }
         node
     }
 
-    pub fn rotate_left(node: Tree) -> Tree {
-        let right_node = node
-            .borrow_mut()
-            .right
-            .take()
+    // The caller relinks the returned subtree root, which also sets its parent
+    fn rotate_left<V>(tree: &mut CommonTree<K, V, B>, node: Link<K, V, B>) -> Link<K, V, B> {
+        let right_node = tree
+            .right(&node)
             .expect("Right node must exist for rotation");
-        let right_left = right_node.borrow_mut().left.take();
+        let right_left = tree.left(&right_node);
+
+        tree.link_right(&node, right_left);
+        tree.link_left(&right_node, Some(node.clone()));
+
+        tree.update_size(&node);
+        tree.update_size(&right_node);
 
-        let node_parent = node.borrow().parent.clone();
+        right_node
+    }
 
-        node.borrow_mut().right = right_left.clone();
+    fn rotate_right<V>(tree: &mut CommonTree<K, V, B>, node: Link<K, V, B>) -> Link<K, V, B> {
+        let left_node = tree.left(&node).expect("Left node must exist for rotation");
+        let left_right = tree.right(&left_node);
 
-        if let Some(right_left) = right_left {
-            right_left.borrow_mut().parent = Some(node.clone());
+        tree.link_left(&node, left_right);
+        tree.link_right(&left_node, Some(node.clone()));
+
+        tree.update_size(&node);
+        tree.update_size(&left_node);
+
+        left_node
+    }
+
+    /// Checks every property of an AVL tree: the order of the keys, the cached heights and
+    /// subtree sizes, the parent links and the balance of every node. All broken properties
+    /// are reported.
+    pub fn validate(&self) -> Result<(), Vec<InvariantViolation<K>>>
+    where
+        K: Clone,
+    {
+        let mut violations = Vec::new();
+        self.tree.check_root(&mut violations);
+        Self::validate_subtree(&self.tree, &self.tree.root, None, None, &mut violations);
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(violations)
+        }
+    }
+
+    // Returns the actual height of the subtree
+    fn validate_subtree(
+        tree: &CommonTree<K, (), B>,
+        node: &Option<Link<K, (), B>>,
+        lower: Option<&K>,
+        upper: Option<&K>,
+        violations: &mut Vec<InvariantViolation<K>>,
+    ) -> i32
+    where
+        K: Clone,
+    {
+        let Some(node) = node else {
+            return 0;
+        };
+        tree.check_node(node, lower, upper, violations);
+        let key = tree.key(node);
+        let left = Self::validate_subtree(tree, &tree.left(node), lower, Some(key), violations);
+        let right = Self::validate_subtree(tree, &tree.right(node), Some(key), upper, violations);
+        let height = 1 + left.max(right);
+        if tree.height(node) != height {
+            violations.push(tree.violation(node, Invariant::Height));
         }
+        if (left - right).abs() > 1 {
+            violations.push(tree.violation(node, Invariant::Balance));
+        }
+        height
+    }
 
-        right_node.borrow_mut().left = Some(node.clone());
-        right_node.borrow_mut().parent = node_parent.clone();
+    /// Removes `key` and returns it, or `None` if it was not present.
+    pub fn delete(&mut self, key: &K) -> Option<K> {
+        let removed = Self::remove_by_key(&mut self.tree, key)?;
+        self.tree.len -= 1;
+        Some(self.tree.free_node(removed).0)
+    }
 
-        // Parent's pointers
-        if let Some(parent) = node_parent {
-            let mut parent_borrow_mut = parent.borrow_mut();
-            if let Some(ref parent_right) = parent_borrow_mut.right {
-                if Rc::ptr_eq(&node, parent_right) {
-                    parent_borrow_mut.right = Some(right_node.clone());
-                }
-            } else {
-                parent_borrow_mut.left = Some(right_node.clone());
+    /// Removes and returns the key at position `index` in ascending order.
+    pub fn remove_at(&mut self, index: usize) -> Option<K> {
+        let removed = Self::remove_by_index(&mut self.tree, index)?;
+        self.tree.len -= 1;
+        Some(self.tree.free_node(removed).0)
+    }
+
+    /// Moves every key that is larger than or equal to `key` into a new tree. Takes
+    /// logarithmic time with `RcBackend`. With `ArenaBackend` the smaller half is also copied
+    /// into an arena of its own, which takes time proportional to its size.
+    pub fn split_off(&mut self, key: &K) -> Self {
+        let root = self.tree.root.take();
+        let total = self.tree.len;
+        let (smaller, found, rest) =
+            Self::split_links(&mut self.tree, root, &|tree, node| key.cmp(tree.key(node)));
+        let rest = match found {
+            Some(node) => Some(Self::join_links(&mut self.tree, None, node, rest)),
+            None => rest,
+        };
+        let smaller = Self::into_root(&mut self.tree, smaller);
+        let rest = Self::into_root(&mut self.tree, rest);
+        let mut other = Self::default();
+        // Only the smaller half is moved to new storage. When that half is the one staying
+        // here, the two stores trade places afterwards.
+        if self.tree.subtree_size(&rest) <= self.tree.subtree_size(&smaller) {
+            self.tree.root = smaller;
+            other.tree.root = other.tree.adopt(&mut self.tree, rest);
+        } else {
+            other.tree.root = rest;
+            self.tree.root = other.tree.adopt(&mut self.tree, smaller);
+            std::mem::swap(&mut self.tree.store, &mut other.tree.store);
+        }
+        self.tree.len = self.tree.subtree_size(&self.tree.root);
+        other.tree.len = total - self.tree.len;
+        other
+    }
+
+    /// Moves every key of `other` into this tree, leaving `other` empty. When all keys of one
+    /// tree are smaller than all keys of the other the two trees are joined, which costs what
+    /// [`Self::join`] costs. Otherwise the keys of `other` are inserted one at a time.
+    pub fn append(&mut self, other: &mut Self) {
+        let mut other = std::mem::take(other);
+        if self.tree.is_empty() {
+            *self = other;
+            return;
+        }
+        if other.tree.is_empty() {
+            return;
+        }
+        // The end nodes are only looked at here, as nodes cannot be freed while linked elsewhere
+        let (precedes, follows) = {
+            let (first, last) = (self.tree.first_node(), self.tree.last_node());
+            let (other_first, other_last) = (other.tree.first_node(), other.tree.last_node());
+            (
+                self.tree.key(&last.unwrap()) < other.tree.key(&other_first.unwrap()),
+                other.tree.key(&other_last.unwrap()) < self.tree.key(&first.unwrap()),
+            )
+        };
+        if precedes {
+            let pivot = other.pop_first().unwrap();
+            *self = Self::join(std::mem::take(self), pivot, other);
+        } else if follows {
+            let pivot = other.pop_last().unwrap();
+            *self = Self::join(other, pivot, std::mem::take(self));
+        } else {
+            for key in other {
+                self.insert(key);
             }
         }
+    }
 
-        CommonTree::update_height(&node);
-        CommonTree::update_height(&right_node);
+    /// Builds a tree from `left`, `pivot` and `right`, where every key of `left` is smaller
+    /// than `pivot` and every key of `right` is larger. With `RcBackend` this takes time
+    /// proportional to the difference of the two trees' heights. With `ArenaBackend` the
+    /// smaller tree is also copied into the arena of the larger one, which takes time
+    /// proportional to its size.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the keys are not ordered that way.
+    pub fn join(mut left: Self, pivot: K, mut right: Self) -> Self {
+        assert!(
+            left.tree
+                .last_node()
+                .is_none_or(|last| *left.tree.key(&last) < pivot)
+                && right
+                    .tree
+                    .first_node()
+                    .is_none_or(|first| pivot < *right.tree.key(&first)),
+            "Keys of the joined trees must be ordered around the pivot"
+        );
+        let len = left.tree.len + 1 + right.tree.len;
+        // The smaller tree is moved into the storage of the larger one
+        let (mut joined, left_root, right_root) = if left.tree.len >= right.tree.len {
+            let right_root = right.tree.root.take();
+            let right_root = left.tree.adopt(&mut right.tree, right_root);
+            let left_root = left.tree.root.take();
+            (left, left_root, right_root)
+        } else {
+            let left_root = left.tree.root.take();
+            let left_root = right.tree.adopt(&mut left.tree, left_root);
+            let right_root = right.tree.root.take();
+            (right, left_root, right_root)
+        };
+        let pivot = joined.tree.new_node(pivot, ());
+        let root = Self::join_links(&mut joined.tree, left_root, pivot, right_root);
+        joined.tree.set_root(Some(root));
+        joined.tree.len = len;
+        joined
+    }
 
-        right_node
+    // Links two subtrees under a pivot that sorts between them. The pivot goes as far down the
+    // spine of the taller subtree as needed to meet the other one at a similar height, and the
+    // path back up is rebalanced like after an insertion.
+    fn join_links<V>(
+        tree: &mut CommonTree<K, V, B>,
+        left: Option<Link<K, V, B>>,
+        pivot: Link<K, V, B>,
+        right: Option<Link<K, V, B>>,
+    ) -> Link<K, V, B> {
+        let left_height = tree.subtree_height(&left);
+        let right_height = tree.subtree_height(&right);
+        if left_height > right_height + 1 {
+            let left = left.unwrap();
+            let inner = tree.right(&left);
+            let joined = Self::join_links(tree, inner, pivot, right);
+            tree.link_right(&left, Some(joined));
+            Self::balance(tree, left)
+        } else if right_height > left_height + 1 {
+            let right = right.unwrap();
+            let inner = tree.left(&right);
+            let joined = Self::join_links(tree, left, pivot, inner);
+            tree.link_left(&right, Some(joined));
+            Self::balance(tree, right)
+        } else {
+            tree.link_left(&pivot, left);
+            tree.link_right(&pivot, right);
+            Self::balance(tree, pivot)
+        }
     }
 
-    pub fn rotate_right(node: Tree) -> Tree {
-        let left_node = node
-            .borrow_mut()
-            .left
-            .take()
-            .expect("Left node must exist for rotation");
-        let left_right = left_node.borrow_mut().right.take();
+    // Splits a subtree around the key that `target` compares nodes against, by joining the
+    // pieces left and right of the search path back together on the way up. A node holding that
+    // key is handed back unlinked.
+    fn split_links<V>(
+        tree: &mut CommonTree<K, V, B>,
+        node: Option<Link<K, V, B>>,
+        target: &impl Fn(&CommonTree<K, V, B>, &Link<K, V, B>) -> Ordering,
+    ) -> Split<K, V, B> {
+        let Some(node) = node else {
+            return (None, None, None);
+        };
+        let (left, right) = (tree.left(&node), tree.right(&node));
+        match target(tree, &node) {
+            Ordering::Greater => {
+                let (smaller, found, larger) = Self::split_links(tree, right, target);
+                (
+                    Some(Self::join_links(tree, left, node, smaller)),
+                    found,
+                    larger,
+                )
+            }
+            Ordering::Less => {
+                let (smaller, found, larger) = Self::split_links(tree, left, target);
+                (
+                    smaller,
+                    found,
+                    Some(Self::join_links(tree, larger, node, right)),
+                )
+            }
+            Ordering::Equal => {
+                tree.set_left(&node, None);
+                tree.set_right(&node, None);
+                (left, Some(node), right)
+            }
+        }
+    }
 
-        let node_parent = node.borrow().parent.clone();
+    /// Removes and returns the smallest key.
+    pub fn pop_first(&mut self) -> Option<K> {
+        self.remove_at(0)
+    }
 
-        node.borrow_mut().left = left_right.clone();
+    /// Removes and returns the largest key.
+    pub fn pop_last(&mut self) -> Option<K> {
+        let last = self.tree.len().checked_sub(1)?;
+        self.remove_at(last)
+    }
+
+    /// Iterates in ascending order over the keys in this tree, in `other` or in both, where
+    /// `other` is a tree of either kind.
+    pub fn union<'a>(
+        &'a self,
+        other: impl IntoIterator<IntoIter = Iter<'a, K, (), B>>,
+    ) -> Union<'a, K, B> {
+        Union::new(self.iter(), other.into_iter())
+    }
+
+    /// Iterates in ascending order over the keys in both this tree and `other`.
+    pub fn intersection<'a>(
+        &'a self,
+        other: impl IntoIterator<IntoIter = Iter<'a, K, (), B>>,
+    ) -> Intersection<'a, K, B> {
+        Intersection::new(self.iter(), other.into_iter())
+    }
+
+    /// Iterates in ascending order over the keys in this tree that are not in `other`.
+    pub fn difference<'a>(
+        &'a self,
+        other: impl IntoIterator<IntoIter = Iter<'a, K, (), B>>,
+    ) -> Difference<'a, K, B> {
+        Difference::new(self.iter(), other.into_iter())
+    }
 
-        if let Some(left_right) = left_right {
-            left_right.borrow_mut().parent = Some(node.clone());
+    /// Iterates in ascending order over the keys in exactly one of this tree and `other`.
+    pub fn symmetric_difference<'a>(
+        &'a self,
+        other: impl IntoIterator<IntoIter = Iter<'a, K, (), B>>,
+    ) -> SymmetricDifference<'a, K, B> {
+        SymmetricDifference::new(self.iter(), other.into_iter())
+    }
+
+    /// Builds the tree of keys in this tree, in `other` or in both, reusing the nodes of both.
+    ///
+    /// With `other` of the same kind this takes O(m log(n / m + 1)) time for trees of m and
+    /// n >= m keys, which beats merging when one tree is much smaller. A red-black tree is
+    /// rebalanced as an AVL tree first, in time linear in its size.
+    /// Keys held by both trees are kept from this one.
+    pub fn into_union(self, other: impl Into<Self>) -> Self {
+        self.combine(other.into(), set_ops::union::<K, B, Self>)
+    }
+
+    /// Builds the tree of keys in both this tree and `other`, with the costs of
+    /// [`Self::into_union`] plus dropping the keys that are left out.
+    pub fn into_intersection(self, other: impl Into<Self>) -> Self {
+        self.combine(other.into(), set_ops::intersection::<K, B, Self>)
+    }
+
+    /// Builds the tree of keys in this tree that are not in `other`, with the costs of
+    /// [`Self::into_union`] plus dropping the keys that are left out.
+    pub fn into_difference(self, other: impl Into<Self>) -> Self {
+        self.combine(other.into(), set_ops::difference::<K, B, Self>)
+    }
+
+    /// Builds the tree of keys in exactly one of this tree and `other`, with the costs of
+    /// [`Self::into_union`] plus dropping the keys that are left out.
+    pub fn into_symmetric_difference(self, other: impl Into<Self>) -> Self {
+        self.combine(other.into(), set_ops::symmetric_difference::<K, B, Self>)
+    }
+
+    fn combine(self, other: Self, operation: Operation<K, B, Self>) -> Self {
+        AVLTree {
+            tree: set_ops::combine::<K, B, Self>(self.tree, other.tree, operation),
         }
+    }
 
-        left_node.borrow_mut().right = Some(node.clone());
-        left_node.borrow_mut().parent = node_parent.clone();
+    // Unlinks a node with the given key, without freeing it
+    fn remove_by_key<V>(tree: &mut CommonTree<K, V, B>, key: &K) -> Option<Link<K, V, B>> {
+        let mut removed = None;
+        let root = tree.root.take();
+        let root = Self::delete_recursive(
+            tree,
+            root,
+            &mut |tree, node| key.cmp(tree.key(node)),
+            &mut removed,
+        );
+        tree.set_root(root);
+        removed
+    }
+
+    // Unlinks `node` from the tree, rebalances it and frees the node
+    pub(crate) fn delete_node<V>(tree: &mut CommonTree<K, V, B>, node: Link<K, V, B>) -> (K, V) {
+        let mut removed = None;
+        let root = tree.root.take();
+        let root = Self::delete_recursive(
+            tree,
+            root,
+            &mut |tree, current| tree.key(&node).cmp(tree.key(current)),
+            &mut removed,
+        );
+        tree.set_root(root);
+        tree.len -= 1;
+        // `removed` is another link to `node`, and nodes are only freed through their last link
+        assert!(
+            removed.take() == Some(node.clone()),
+            "Deleted node must be in the tree"
+        );
+        tree.free_node(node)
+    }
 
-        // Parent's pointers
-        if let Some(parent) = node_parent {
-            let mut parent_borrow_mut = parent.borrow_mut();
-            if let Some(ref parent_left) = parent_borrow_mut.left {
-                if Rc::ptr_eq(&node, parent_left) {
-                    parent_borrow_mut.left = Some(left_node.clone());
+    // Unlinks the node at position `index`, without freeing it
+    fn remove_by_index<V>(
+        tree: &mut CommonTree<K, V, B>,
+        mut index: usize,
+    ) -> Option<Link<K, V, B>> {
+        let mut removed = None;
+        let root = tree.root.take();
+        let root = Self::delete_recursive(
+            tree,
+            root,
+            &mut |tree, node| {
+                let left_size = tree.left(node).map_or(0, |left| tree.size(&left));
+                let ordering = index.cmp(&left_size);
+                if ordering == Ordering::Greater {
+                    index -= left_size + 1;
                 }
-            } else {
-                parent_borrow_mut.right = Some(left_node.clone());
+                ordering
+            },
+            &mut removed,
+        );
+        tree.set_root(root);
+        removed
+    }
+
+    // `target` tells on which side of a node the node to delete lies, and is asked once per
+    // level on the way down. `removed` receives the unlinked node, if there is one.
+    fn delete_recursive<V>(
+        tree: &mut CommonTree<K, V, B>,
+        node: Option<Link<K, V, B>>,
+        target: &mut impl FnMut(&CommonTree<K, V, B>, &Link<K, V, B>) -> Ordering,
+        removed: &mut Option<Link<K, V, B>>,
+    ) -> Option<Link<K, V, B>> {
+        let current_node = node?;
+        let ordering = target(tree, &current_node);
+        if ordering == Ordering::Less {
+            let left = tree.left(&current_node);
+            let left = Self::delete_recursive(tree, left, target, removed);
+            tree.link_left(&current_node, left);
+        } else if ordering == Ordering::Greater {
+            let right = tree.right(&current_node);
+            let right = Self::delete_recursive(tree, right, target, removed);
+            tree.link_right(&current_node, right);
+        } else {
+            let (left, right) = (tree.left(&current_node), tree.right(&current_node));
+            tree.set_left(&current_node, None);
+            tree.set_right(&current_node, None);
+            *removed = Some(current_node);
+
+            // Node with only one child or no child
+            let (left, right) = match (left, right) {
+                (None, right) => return right,
+                (left, None) => return left,
+                (Some(left), Some(right)) => (left, right),
+            };
+
+            // Node with two children: the inorder successor (smallest in the right subtree)
+            // is unlinked and takes the place of the deleted node
+            let mut successor = None;
+            let right = Self::remove_min(tree, right, &mut successor);
+            let successor = successor.unwrap();
+            tree.link_left(&successor, Some(left));
+            tree.link_right(&successor, right);
+            return Some(Self::balance(tree, successor));
+        }
+        Some(Self::balance(tree, current_node))
+    }
+
+    // Detaches the smallest node of the subtree into `min`, returning the rebalanced remainder
+    fn remove_min<V>(
+        tree: &mut CommonTree<K, V, B>,
+        node: Link<K, V, B>,
+        min: &mut Option<Link<K, V, B>>,
+    ) -> Option<Link<K, V, B>> {
+        match tree.left(&node) {
+            Some(left) => {
+                let new_left = Self::remove_min(tree, left, min);
+                tree.link_left(&node, new_left);
+                Some(Self::balance(tree, node))
+            }
+            None => {
+                let right = tree.right(&node);
+                tree.set_right(&node, None);
+                *min = Some(node);
+                right
             }
         }
+    }
+}
 
-        CommonTree::update_height(&node);
-        CommonTree::update_height(&left_node);
+impl<'a, K: Ord, B: Backend> IntoIterator for &'a AVLTree<K, B> {
+    type Item = &'a K;
+    type IntoIter = Iter<'a, K, (), B>;
 
-        left_node
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
     }
+}
+
+impl<K: Ord, B: Backend> IntoIterator for AVLTree<K, B> {
+    type Item = K;
+    type IntoIter = IntoIter<K, (), B>;
 
-    pub fn delete(&mut self, key: i32) {
-        self.tree.root = Self::delete_recursive(self.tree.root.take(), key);
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter::new(self.tree)
     }
+}
 
-    fn delete_recursive(node: GenericTree, key: i32) -> GenericTree {
-        if let Some(current_node) = node.clone() {
-            let mut current = current_node.borrow_mut();
-            if key < current.key {
-                current.left = Self::delete_recursive(current.left.take(), key);
-            } else if key > current.key {
-                current.right = Self::delete_recursive(current.right.take(), key);
-            } else {
-                // Node with only one child or no child
-                if current.left.is_none() {
-                    return current.right.take();
-                } else if current.right.is_none() {
-                    return current.left.take();
-                }
+impl<K: Ord, B: Backend> From<RedBlackTree<K, B>> for AVLTree<K, B> {
+    /// Relinks the nodes of a red-black tree into a balanced AVL tree, in O(n).
+    fn from(tree: RedBlackTree<K, B>) -> Self {
+        let mut tree = tree.tree;
+        tree.rebuild_balanced(false);
+        AVLTree { tree }
+    }
+}
+
+impl<K: Ord, B: Backend> Join<K, B> for AVLTree<K, B> {
+    type Subtree = Option<Link<K, (), B>>;
+
+    fn subtree(_tree: &CommonTree<K, (), B>, root: Option<Link<K, (), B>>) -> Self::Subtree {
+        root
+    }
+
+    fn into_root(
+        tree: &mut CommonTree<K, (), B>,
+        subtree: Self::Subtree,
+    ) -> Option<Link<K, (), B>> {
+        if let Some(root) = &subtree {
+            tree.set_parent(root, None);
+        }
+        subtree
+    }
+
+    fn is_empty(subtree: &Self::Subtree) -> bool {
+        subtree.is_none()
+    }
+
+    fn expose(
+        tree: &mut CommonTree<K, (), B>,
+        subtree: Self::Subtree,
+    ) -> (Self::Subtree, Link<K, (), B>, Self::Subtree) {
+        let node = subtree.expect("Exposed subtree must not be empty");
+        let (left, right) = (tree.left(&node), tree.right(&node));
+        tree.set_left(&node, None);
+        tree.set_right(&node, None);
+        (left, node, right)
+    }
+
+    fn join(
+        tree: &mut CommonTree<K, (), B>,
+        left: Self::Subtree,
+        pivot: Link<K, (), B>,
+        right: Self::Subtree,
+    ) -> Self::Subtree {
+        Some(Self::join_links(tree, left, pivot, right))
+    }
+
+    fn split(
+        tree: &mut CommonTree<K, (), B>,
+        subtree: Self::Subtree,
+        pivot: &Link<K, (), B>,
+    ) -> Split<K, (), B> {
+        Self::split_links(tree, subtree, &|tree, node| {
+            tree.key(pivot).cmp(tree.key(node))
+        })
+    }
+}
+
+impl<K: Ord, B: Backend> FromIterator<K> for AVLTree<K, B> {
+    /// Sorts the keys and builds the tree in one pass. Of equal keys, the first one is kept.
+    fn from_iter<I: IntoIterator<Item = K>>(iter: I) -> Self {
+        let mut keys: Vec<K> = iter.into_iter().collect();
+        keys.sort();
+        keys.dedup();
+        Self::from_sorted_iter(keys).expect("sorted and deduplicated keys are ascending")
+    }
+}
+
+impl<K: Ord, B: Backend> Extend<K> for AVLTree<K, B> {
+    /// Builds a tree of the new keys and unites it with this one, keeping the keys already
+    /// present.
+    fn extend<I: IntoIterator<Item = K>>(&mut self, iter: I) {
+        let keys: Self = iter.into_iter().collect();
+        *self = std::mem::take(self).into_union(keys);
+    }
+}
+
+impl<'a, K: Ord + Copy + 'a, B: Backend> Extend<&'a K> for AVLTree<K, B> {
+    fn extend<I: IntoIterator<Item = &'a K>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().copied());
+    }
+}
+
+impl<K: Ord + Clone, B: Backend> OrderedSet<K> for AVLTree<K, B> {
+    fn insert(&mut self, key: K) -> bool {
+        AVLTree::insert(self, key)
+    }
+
+    fn remove(&mut self, key: &K) -> Option<K> {
+        self.delete(key)
+    }
+
+    fn contains(&self, key: &K) -> bool {
+        AVLTree::contains(self, key)
+    }
+
+    fn len(&self) -> usize {
+        AVLTree::len(self)
+    }
+
+    fn is_empty(&self) -> bool {
+        AVLTree::is_empty(self)
+    }
+
+    fn height(&self) -> i32 {
+        AVLTree::height(self)
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = &K> + '_> {
+        Box::new(AVLTree::iter(self))
+    }
+
+    fn range(&self, start: Bound<&K>, end: Bound<&K>) -> Box<dyn Iterator<Item = &K> + '_> {
+        Box::new(AVLTree::range(self, (start, end)))
+    }
 
-                // Node with two children: Get the inorder successor (smallest in the right subtree)
-                let temp = Self::min_value_node(current.right.as_ref().unwrap());
-                current.key = temp.borrow().key;
-                current.right = Self::delete_recursive(current.right.take(), current.key);
+    fn min(&self) -> Option<K> {
+        self.tree.min()
+    }
+
+    fn max(&self) -> Option<K> {
+        self.tree.max()
+    }
+
+    fn inorder(&self) -> Vec<K> {
+        self.tree.print_inorder()
+    }
+
+    fn preorder(&self) -> Vec<K> {
+        self.tree.print_preorder()
+    }
+
+    fn postorder(&self) -> Vec<K> {
+        self.tree.print_postorder()
+    }
+
+    fn count_leaves(&self) -> i32 {
+        self.tree.count_leaves()
+    }
+
+    fn print_structure(&self)
+    where
+        K: Display,
+    {
+        self.tree.print_structure()
+    }
+}
+
+impl<K: Ord, V, B: Backend> Default for AVLMap<K, V, B> {
+    fn default() -> Self {
+        AVLMap {
+            tree: CommonTree::default(),
+        }
+    }
+}
+
+impl<K: Ord, V> AVLMap<K, V> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<K: Ord, V, B: Backend> AVLMap<K, V, B> {
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    /// Inserts a key-value pair, returning the previous value if the key was present.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        match self.entry(key) {
+            Entry::Occupied(mut entry) => Some(entry.insert(value)),
+            Entry::Vacant(entry) => {
+                entry.insert(value);
+                None
             }
+        }
+    }
 
-            // Existing balance function is called here
-            //Some(Self::balance(current_node))
+    /// Looks up `key` once, for inserting it or updating its value without another search.
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V, B> {
+        Entry::new(&mut self.tree, key, AVLTree::attach)
+    }
+
+    /// Returns a cursor at the smallest key, or past the ends if the tree is empty.
+    pub fn cursor_front(&self) -> Cursor<'_, K, V, B> {
+        Cursor::new(&self.tree, self.tree.first_node())
+    }
+
+    /// Returns a cursor at the largest key, or past the ends if the tree is empty.
+    pub fn cursor_back(&self) -> Cursor<'_, K, V, B> {
+        Cursor::new(&self.tree, self.tree.last_node())
+    }
+
+    /// Returns a cursor at the smallest key within `bound`, or past the ends if there is none.
+    pub fn lower_bound(&self, bound: Bound<&K>) -> Cursor<'_, K, V, B> {
+        Cursor::new(&self.tree, self.tree.first_node_after(bound))
+    }
+
+    pub fn cursor_front_mut(&mut self) -> CursorMut<'_, K, V, B> {
+        let first = self.tree.first_node();
+        CursorMut::new(&mut self.tree, first, AVLTree::attach, AVLTree::delete_node)
+    }
+
+    pub fn cursor_back_mut(&mut self) -> CursorMut<'_, K, V, B> {
+        let last = self.tree.last_node();
+        CursorMut::new(&mut self.tree, last, AVLTree::attach, AVLTree::delete_node)
+    }
+
+    pub fn lower_bound_mut(&mut self, bound: Bound<&K>) -> CursorMut<'_, K, V, B> {
+        let node = self.tree.first_node_after(bound);
+        CursorMut::new(&mut self.tree, node, AVLTree::attach, AVLTree::delete_node)
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        let node = self.tree.search(key)?;
+        Some(self.tree.value(&node))
+    }
+
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        let node = self.tree.search(key)?;
+        Some(self.tree.value_mut(&node))
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.tree.search(key).is_some()
+    }
+
+    /// Removes a key from the map, returning its value if the key was present.
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let removed = AVLTree::remove_by_key(&mut self.tree, key)?;
+        self.tree.len -= 1;
+        Some(self.tree.free_node(removed).1)
+    }
+}
+
+impl<K: Ord, V, A: Augment<K, V>, B: Backend> Default for AVLAugmentedMap<K, V, A, B> {
+    fn default() -> Self {
+        AVLAugmentedMap {
+            tree: augment::tree::<K, V, A, B>(),
+        }
+    }
+}
+
+impl<K: Ord, V, A: Augment<K, V>> AVLAugmentedMap<K, V, A> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<K: Ord, V, A: Augment<K, V>, B: Backend> AVLAugmentedMap<K, V, A, B> {
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    /// Inserts a key-value pair, returning the previous value if the key was present.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        augment::insert::<K, V, A, B>(&mut self.tree, key, value, AVLTree::attach)
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        let node = self.tree.search(key)?;
+        Some(&self.tree.value(&node).0)
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.tree.search(key).is_some()
+    }
+
+    /// Removes a key from the map, returning its value if the key was present.
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let removed = AVLTree::remove_by_key(&mut self.tree, key)?;
+        self.tree.len -= 1;
+        Some(self.tree.free_node(removed).1 .0)
+    }
+
+    /// Combines the summaries of all entries in key order, in O(1).
+    pub fn fold(&self) -> A::Summary {
+        augment::summary::<K, V, A, B>(&self.tree, &self.tree.root)
+    }
+
+    /// Combines the summaries of the entries whose keys fall within `range` in key order, in
+    /// O(log n).
+    pub fn fold_range<R: RangeBounds<K>>(&self, range: R) -> A::Summary {
+        augment::fold_range::<K, V, A, B, R>(&self.tree, range)
+    }
+}
+
+impl<K: Ord, B: Backend> Default for AVLMultiset<K, B> {
+    fn default() -> Self {
+        AVLMultiset {
+            tree: CommonTree::default(),
+            len: 0,
+        }
+    }
+}
+
+impl<K: Ord> AVLMultiset<K> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<K: Ord, B: Backend> AVLMultiset<K, B> {
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of keys, counting every copy.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns how many copies of `key` the multiset holds.
+    pub fn count(&self, key: &K) -> usize {
+        self.tree
+            .search(key)
+            .map_or(0, |node| *self.tree.value(&node))
+    }
+
+    pub fn contains(&self, key: &K) -> bool {
+        self.tree.search(key).is_some()
+    }
+
+    /// Adds one copy of `key`.
+    pub fn insert(&mut self, key: K) {
+        *Entry::new(&mut self.tree, key, AVLTree::attach).or_insert(0) += 1;
+        self.len += 1;
+    }
+
+    /// Removes one copy of `key`, returning whether there was one.
+    pub fn remove_one(&mut self, key: &K) -> bool {
+        let Some(node) = self.tree.search(key) else {
+            return false;
+        };
+        if *self.tree.value(&node) > 1 {
+            *self.tree.value_mut(&node) -= 1;
         } else {
-            return None;
+            // No link may be held on to while its node is freed
+            drop(node);
+            let removed = AVLTree::remove_by_key(&mut self.tree, key).unwrap();
+            self.tree.len -= 1;
+            self.tree.free_node(removed);
         }
-        Some(Self::balance(node?))
+        self.len -= 1;
+        true
     }
 
-    fn min_value_node(node: &Tree) -> Tree {
-        match &node.borrow().left {
-            Some(left) => Self::min_value_node(left),
-            None => node.clone(),
+    /// Removes every copy of `key`, returning how many there were.
+    pub fn remove_all(&mut self, key: &K) -> usize {
+        let Some(removed) = AVLTree::remove_by_key(&mut self.tree, key) else {
+            return 0;
+        };
+        self.tree.len -= 1;
+        let count = self.tree.free_node(removed).1;
+        self.len -= count;
+        count
+    }
+
+    /// Iterates over the keys in ascending order, repeating each key for every copy.
+    pub fn iter(&self) -> MultiIter<'_, K, B> {
+        MultiIter::new(&self.tree, self.len)
+    }
+}
+
+impl<'a, K: Ord, B: Backend> IntoIterator for &'a AVLMultiset<K, B> {
+    type Item = &'a K;
+    type IntoIter = MultiIter<'a, K, B>;
+
+    fn into_iter(self) -> MultiIter<'a, K, B> {
+        self.iter()
+    }
+}
+
+impl<K: Ord + Clone, B: Backend> IntoIterator for AVLMultiset<K, B> {
+    type Item = K;
+    type IntoIter = MultiIntoIter<K, B>;
+
+    fn into_iter(self) -> MultiIntoIter<K, B> {
+        MultiIntoIter::new(self.tree, self.len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AVLTree;
+    use crate::error::{Invariant, InvariantViolation};
+
+    fn violation(key: i32, invariant: Invariant) -> InvariantViolation<i32> {
+        InvariantViolation { key, invariant }
+    }
+
+    #[test]
+    fn broken_avl_properties_are_reported() {
+        let mut tree = AVLTree::new();
+        for key in 1..=3 {
+            tree.insert(key);
         }
+        let nodes = &mut tree.tree;
+        let root = nodes.root.clone().unwrap();
+        nodes.set_height(&root, 5);
+        assert_eq!(tree.validate(), Err(vec![violation(2, Invariant::Height)]));
+
+        // Moves 3 below 1 on the wrong side, which also unbalances the root
+        let nodes = &mut tree.tree;
+        nodes.set_height(&root, 2);
+        let right = nodes.right(&root).unwrap();
+        nodes.set_right(&root, None);
+        let left = nodes.left(&root).unwrap();
+        nodes.set_left(&left, Some(right.clone()));
+        nodes.set_parent(&right, Some(left.clone()));
+        nodes.set_size(&left, 2);
+        nodes.set_height(&left, 2);
+        nodes.set_height(&root, 3);
+        assert_eq!(
+            tree.validate(),
+            Err(vec![
+                violation(3, Invariant::Order),
+                violation(2, Invariant::Balance),
+            ])
+        );
+
+        let nodes = &mut tree.tree;
+        nodes.set_parent(&right, Some(root.clone()));
+        nodes.set_parent(&root, Some(left.clone()));
+        assert_eq!(
+            tree.validate(),
+            Err(vec![
+                violation(2, Invariant::Parent),
+                violation(3, Invariant::Parent),
+                violation(3, Invariant::Order),
+                violation(2, Invariant::Balance),
+            ])
+        );
     }
 }
@@ -1,5 +1,4 @@
-use crate::avl_tree::AVLTree;
-use crate::rb_tree::RedBlackTree;
+use baltree::{AVLTree, OrderedSet, RedBlackTree};
 use std::io;
 
 fn parse_keys(input: &str) -> (Vec<i32>, bool) {
@@ -20,17 +19,18 @@ fn parse_keys(input: &str) -> (Vec<i32>, bool) {
     (keys, invalid_input_encountered)
 }
 
-fn avl_interface() {
-    println!("AVL Tree Created!");
-    let mut avl = AVLTree::new();
+fn tree_interface(name: &str, short_name: &str, tree: &mut dyn OrderedSet<i32>) {
+    println!("{} Created!", name);
+    let title = format!("| Enter {} Command |", name);
+    let border = format!("+{}+", "-".repeat(title.len() - 2));
     loop {
-        println!("+------------------------+");
-        println!("| Enter AVL Tree Command |");
-        println!("+------------------------+");
-        println!("1: Add Keys to AVL\n2: Delete Key from AVL\n3: Find the number of leaves\n4: Find the height of tree\n5: Print In-Order Tree\n6: Print Pre-Order Tree\n7: Print Post-Order Tree\n8: Check if Tree is empty\n9: Print Tree Structure\n10: Exit to Main Menu");
+        println!("{}", border);
+        println!("{}", title);
+        println!("{}", border);
+        println!("1: Add Keys to {short_name}\n2: Delete Key from {short_name}\n3: Find the number of leaves\n4: Find the height of tree\n5: Print In-Order Tree\n6: Print Pre-Order Tree\n7: Print Post-Order Tree\n8: Check if Tree is empty\n9: Print Tree Structure\n10: Exit to Main Menu");
 
         let mut input = String::new();
-        input.clear();
+        input.clear(); // Clear the input buffer before reading a new value
         io::stdin()
             .read_line(&mut input)
             .expect("Failed to read line");
@@ -52,111 +52,10 @@ fn avl_interface() {
                     }
                 } else {
                     for k in keys {
-                        if avl.tree.search(k).is_some() {
-                            println!("Key {} already exists", k);
-                        } else {
-                            avl.insert(k);
+                        if tree.insert(k) {
                             println!("Key {} inserted.", k);
-                        }
-                    }
-                    if invalid_input_encountered {
-                        println!("Some inputs were not valid numbers and were ignored.");
-                    }
-                }
-            }
-            "2" => {
-                println!("Enter Key to Delete: ");
-                input.clear(); // Clear the input buffer before reading a new value
-                io::stdin()
-                    .read_line(&mut input)
-                    .expect("Failed to read line");
-                let key = input.trim().parse::<i32>(); // Attempt to parse the input as an integer
-
-                match key {
-                    Ok(k) => {
-                        if avl.tree.search(k).is_some() {
-                            avl.delete(k);
-                            println!("Key {} deleted.", k);
                         } else {
-                            println!("Key does not exist");
-                        }
-                    }
-                    Err(_) => println!("Please enter a valid integer."),
-                }
-            }
-            "3" => {
-                println!("The number of leaves is: {}", avl.tree.count_leaves());
-            }
-            "4" => {
-                println!("The height of the tree is: {}", avl.tree.get_height());
-            }
-            "5" => {
-                println!("The tree when in-order is: {:?}", avl.tree.print_inorder());
-            }
-            "6" => {
-                println!(
-                    "The tree when pre-order is: {:?}",
-                    avl.tree.print_preorder()
-                );
-            }
-            "7" => {
-                println!(
-                    "The tree when post-order is: {:?}",
-                    avl.tree.print_postorder()
-                );
-            }
-            "8" => {
-                println!("Checking if tree is empty: {}", avl.tree.is_empty());
-            }
-            "9" => {
-                println!("Printing Tree:");
-                avl.tree.print_structure()
-            }
-            "10" => {
-                println!("Returning to Main Menu.");
-                break;
-            }
-            _ => println!("Invalid input, try again!"),
-        }
-    }
-}
-
-fn rb_interface() {
-    println!("Red Black Tree Created!");
-    let mut rbt = RedBlackTree::new();
-    loop {
-        println!("+------------------------------+");
-        println!("| Enter Red Black Tree Command |");
-        println!("+------------------------------+");
-        println!("1: Add Keys to RBT\n2: Delete Key from RBT\n3: Find the number of leaves\n4: Find the height of tree\n5: Print In-Order Tree\n6: Print Pre-Order Tree\n7: Print Post-Order Tree\n8: Check if Tree is empty\n9: Print Tree Structure\n10: Exit to Main Menu");
-
-        let mut input = String::new();
-        input.clear(); // Clear the input buffer before reading a new value
-        io::stdin()
-            .read_line(&mut input)
-            .expect("Failed to read line");
-
-        match input.trim() {
-            "1" => {
-                println!("Enter Keys to Insert (comma or space-separated): ");
-                input.clear();
-                io::stdin()
-                    .read_line(&mut input)
-                    .expect("Failed to read line");
-                let (keys, invalid_input_encountered) = parse_keys(&input);
-                if keys.is_empty() {
-                    if invalid_input_encountered {
-                        println!("No valid numbers found. Please insert a stream of numbers (comma or space-separated).");
-                    } else {
-                        println!("No input provided. Please enter some numbers.");
-                    }
-                } else {
-                    for k in keys {
-                        if rbt.tree.search(k).is_some() {
                             println!("Key {} already exists", k);
-                        } else {
-                            rbt.insert(k);
-                            println!("Key {} inserted.", k);
                         }
                     }
                     if invalid_input_encountered {
@@ -174,9 +73,7 @@ fn rb_interface() {
 
                 match key {
                     Ok(k) => {
-                        if rbt.tree.search(k).is_some() {
-                            // TODO: handle delete
-                            rbt.delete(k);
+                        if tree.remove(&k).is_some() {
                             println!("Key {} deleted.", k);
                         } else {
                             println!("Key does not exist");
@@ -186,32 +83,26 @@ fn rb_interface() {
                 }
             }
             "3" => {
-                println!("The number of leaves is: {}", rbt.tree.count_leaves());
+                println!("The number of leaves is: {}", tree.count_leaves());
             }
             "4" => {
-                println!("The height of the tree is: {}", rbt.tree.get_height());
+                println!("The height of the tree is: {}", tree.height());
             }
             "5" => {
-                println!("The tree when in-order is: {:?}", rbt.tree.print_inorder());
+                println!("The tree when in-order is: {:?}", tree.inorder());
             }
             "6" => {
-                println!(
-                    "The tree when pre-order is: {:?}",
-                    rbt.tree.print_preorder()
-                );
+                println!("The tree when pre-order is: {:?}", tree.preorder());
             }
             "7" => {
-                println!(
-                    "The tree when post-order is: {:?}",
-                    rbt.tree.print_postorder()
-                );
+                println!("The tree when post-order is: {:?}", tree.postorder());
             }
             "8" => {
-                println!("Checking if tree is empty: {}", rbt.tree.is_empty());
+                println!("Checking if tree is empty: {}", tree.is_empty());
             }
             "9" => {
                 println!("Printing Tree:");
-                rbt.tree.print_structure()
+                tree.print_structure()
             }
             "10" => {
                 println!("Returning to Main Menu.");
@@ -238,8 +129,8 @@ pub fn user_input_display() {
             .expect("Failed to read line");
 
         match input.trim() {
-            "1" => avl_interface(),
-            "2" => rb_interface(),
+            "1" => tree_interface("AVL Tree", "AVL", &mut AVLTree::new()),
+            "2" => tree_interface("Red Black Tree", "RBT", &mut RedBlackTree::new()),
             "3" => {
                 println!("Quit");
                 break;
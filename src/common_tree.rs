@@ -1,13 +1,20 @@
+use crate::error::{Invariant, InvariantViolation, TreeError};
+use crate::iter::{after_start, before_end, Iter, Range};
+use crate::storage::{Backend, Link, RcBackend, Storage};
 use colored::*;
-use std::cell::RefCell;
 use std::cmp::max;
-use std::rc::Rc;
+use std::fmt::Display;
+use std::ops::{Bound, RangeBounds};
 
-pub type Tree = Rc<RefCell<Node>>;
-pub type GenericTree = Option<Tree>;
+// Recomputes data that a kind of tree keeps in the value of each node from the node's children
+pub(crate) type Refresh<K, V, B> = fn(&mut CommonTree<K, V, B>, &Link<K, V, B>);
 
-pub struct CommonTree {
-    pub root: GenericTree,
+pub(crate) struct CommonTree<K, V = (), B: Backend = RcBackend> {
+    pub(crate) root: Option<Link<K, V, B>>,
+    pub(crate) store: B::Store<K, V>,
+    pub(crate) len: usize,
+    // Runs after every subtree size update, since those follow every change below a node
+    pub(crate) refresh: Option<Refresh<K, V, B>>,
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -16,64 +23,237 @@ pub enum NodeColor {
     Black,
 }
 
-#[derive(Clone, Debug, PartialEq)]
-pub struct Node {
-    pub key: i32,
-    pub left: GenericTree,
-    pub right: GenericTree,
-    pub parent: GenericTree,
-    pub height: i32,
-    pub color: Option<NodeColor>,
-}
-
-impl CommonTree {
-    pub fn new() -> Self {
-        CommonTree { root: None }
+impl<K: Ord, V, B: Backend> Default for CommonTree<K, V, B> {
+    fn default() -> Self {
+        CommonTree {
+            root: None,
+            store: Default::default(),
+            len: 0,
+            refresh: None,
+        }
     }
+}
 
+impl<K: Ord, V, B: Backend> CommonTree<K, V, B> {
     pub fn is_empty(&self) -> bool {
         self.root.is_none()
     }
 
-    pub fn print_inorder(&self) -> Vec<i32> {
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Iterates over the keys in ascending order.
+    pub fn iter(&self) -> Iter<'_, K, V, B> {
+        Iter::new(self)
+    }
+
+    /// Iterates in ascending order over the keys that fall within `range`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start of the range lies after its end.
+    pub fn range<R: RangeBounds<K>>(&self, range: R) -> Range<'_, K, V, B> {
+        Range::new(self, range)
+    }
+
+    pub fn min(&self) -> Option<K>
+    where
+        K: Clone,
+    {
+        let node = self.first_node()?;
+        Some(self.key(&node).clone())
+    }
+
+    pub fn max(&self) -> Option<K>
+    where
+        K: Clone,
+    {
+        let node = self.last_node()?;
+        Some(self.key(&node).clone())
+    }
+
+    pub(crate) fn first_node(&self) -> Option<Link<K, V, B>> {
+        let mut current = self.root.clone()?;
+        while let Some(left) = self.left(&current) {
+            current = left;
+        }
+        Some(current)
+    }
+
+    pub(crate) fn last_node(&self) -> Option<Link<K, V, B>> {
+        let mut current = self.root.clone()?;
+        while let Some(right) = self.right(&current) {
+            current = right;
+        }
+        Some(current)
+    }
+
+    /// Returns the node that follows `node` in key order, such as one returned by
+    /// [`search`](Self::search), by walking the parent links. Takes O(1) amortized time when
+    /// walking through the whole tree. Callers outside the crate step through nodes with the
+    /// cursors instead, which are built on this.
+    pub(crate) fn next_node(&self, node: &Link<K, V, B>) -> Option<Link<K, V, B>> {
+        if let Some(mut current) = self.right(node) {
+            while let Some(left) = self.left(&current) {
+                current = left;
+            }
+            return Some(current);
+        }
+        let mut child = node.clone();
+        while let Some(parent) = self.parent(&child) {
+            if self.left(&parent).as_ref() == Some(&child) {
+                return Some(parent);
+            }
+            child = parent;
+        }
+        None
+    }
+
+    /// Returns the node that precedes `node` in key order, like [`next_node`](Self::next_node).
+    pub(crate) fn prev_node(&self, node: &Link<K, V, B>) -> Option<Link<K, V, B>> {
+        if let Some(mut current) = self.left(node) {
+            while let Some(right) = self.right(&current) {
+                current = right;
+            }
+            return Some(current);
+        }
+        let mut child = node.clone();
+        while let Some(parent) = self.parent(&child) {
+            if self.right(&parent).as_ref() == Some(&child) {
+                return Some(parent);
+            }
+            child = parent;
+        }
+        None
+    }
+
+    /// Returns the largest key that is smaller than or equal to `key`.
+    pub fn floor(&self, key: &K) -> Option<K>
+    where
+        K: Clone,
+    {
+        self.key_of(self.last_node_before(Bound::Included(key)))
+    }
+
+    /// Returns the smallest key that is larger than or equal to `key`.
+    pub fn ceiling(&self, key: &K) -> Option<K>
+    where
+        K: Clone,
+    {
+        self.key_of(self.first_node_after(Bound::Included(key)))
+    }
+
+    /// Returns the largest key that is strictly smaller than `key`.
+    pub fn predecessor(&self, key: &K) -> Option<K>
+    where
+        K: Clone,
+    {
+        self.key_of(self.last_node_before(Bound::Excluded(key)))
+    }
+
+    /// Returns the smallest key that is strictly larger than `key`.
+    pub fn successor(&self, key: &K) -> Option<K>
+    where
+        K: Clone,
+    {
+        self.key_of(self.first_node_after(Bound::Excluded(key)))
+    }
+
+    // The keys past a bound form a suffix of the in-order sequence, so a single descent finds
+    // where it starts
+    pub(crate) fn first_node_after(&self, start: Bound<&K>) -> Option<Link<K, V, B>> {
+        let mut found = None;
+        let mut current_node = self.root.clone();
+        while let Some(node) = current_node {
+            if after_start(start, self.key(&node)) {
+                current_node = self.left(&node);
+                found = Some(node);
+            } else {
+                current_node = self.right(&node);
+            }
+        }
+        found
+    }
+
+    pub(crate) fn last_node_before(&self, end: Bound<&K>) -> Option<Link<K, V, B>> {
+        let mut found = None;
+        let mut current_node = self.root.clone();
+        while let Some(node) = current_node {
+            if before_end(end, self.key(&node)) {
+                current_node = self.right(&node);
+                found = Some(node);
+            } else {
+                current_node = self.left(&node);
+            }
+        }
+        found
+    }
+
+    fn key_of(&self, node: Option<Link<K, V, B>>) -> Option<K>
+    where
+        K: Clone,
+    {
+        Some(self.key(&node?).clone())
+    }
+
+    pub fn print_inorder(&self) -> Vec<K>
+    where
+        K: Clone,
+    {
         let mut result = Vec::new();
-        Self::inorder_traversal(&self.root, &mut result);
+        self.inorder_traversal(&self.root, &mut result);
         result
     }
 
-    fn inorder_traversal(node: &GenericTree, result: &mut Vec<i32>) {
+    fn inorder_traversal(&self, node: &Option<Link<K, V, B>>, result: &mut Vec<K>)
+    where
+        K: Clone,
+    {
         if let Some(ref node) = node {
-            Self::inorder_traversal(&node.borrow().left, result);
-            result.push(node.borrow().key);
-            Self::inorder_traversal(&node.borrow().right, result);
+            self.inorder_traversal(&self.left(node), result);
+            result.push(self.key(node).clone());
+            self.inorder_traversal(&self.right(node), result);
         }
     }
 
-    pub fn print_postorder(&self) -> Vec<i32> {
+    pub fn print_postorder(&self) -> Vec<K>
+    where
+        K: Clone,
+    {
         let mut result = Vec::new();
-        Self::postorder_traversal(&self.root, &mut result);
+        self.postorder_traversal(&self.root, &mut result);
         result
     }
 
-    fn postorder_traversal(node: &GenericTree, result: &mut Vec<i32>) {
+    fn postorder_traversal(&self, node: &Option<Link<K, V, B>>, result: &mut Vec<K>)
+    where
+        K: Clone,
+    {
         if let Some(ref node) = node {
-            Self::postorder_traversal(&node.borrow().left, result);
-            Self::postorder_traversal(&node.borrow().right, result);
-            result.push(node.borrow().key);
+            self.postorder_traversal(&self.left(node), result);
+            self.postorder_traversal(&self.right(node), result);
+            result.push(self.key(node).clone());
         }
     }
 
-    pub fn print_preorder(&self) -> Vec<i32> {
+    pub fn print_preorder(&self) -> Vec<K>
+    where
+        K: Clone,
+    {
         let mut result = Vec::new();
-        Self::preorder_traversal(&self.root, &mut result);
+        self.preorder_traversal(&self.root, &mut result);
         result
     }
 
-    fn preorder_traversal(node: &GenericTree, result: &mut Vec<i32>) {
+    fn preorder_traversal(&self, node: &Option<Link<K, V, B>>, result: &mut Vec<K>)
+    where
+        K: Clone,
+    {
         if let Some(ref node) = node {
-            result.push(node.borrow().key);
-            Self::preorder_traversal(&node.borrow().left, result);
-            Self::preorder_traversal(&node.borrow().right, result);
+            result.push(self.key(node).clone());
+            self.preorder_traversal(&self.left(node), result);
+            self.preorder_traversal(&self.right(node), result);
         }
     }
 
@@ -81,108 +261,398 @@ impl CommonTree {
         self.height_helper(&self.root)
     }
 
-    fn height_helper(&self, node: &GenericTree) -> i32 {
+    pub(crate) fn height_helper(&self, node: &Option<Link<K, V, B>>) -> i32 {
         match node {
             Some(node) => {
                 1 + max(
-                    self.height_helper(&node.borrow().left),
-                    self.height_helper(&node.borrow().right),
+                    self.height_helper(&self.left(node)),
+                    self.height_helper(&self.right(node)),
                 )
             }
             None => 0,
         }
     }
 
-    pub fn update_height(node: &Tree) {
-        let left_height = node.borrow().left.as_ref().map_or(0, |n| n.borrow().height);
-        let right_height = node
-            .borrow()
-            .right
-            .as_ref()
-            .map_or(0, |n| n.borrow().height);
-        node.borrow_mut().height = 1 + max(left_height, right_height);
+    /// Recomputes the subtree size and height of `node` from its children. Every change below
+    /// a node is followed by this, bottom up, so both stay right in either kind of tree.
+    pub(crate) fn update_size(&mut self, node: &Link<K, V, B>) {
+        let (left, right) = (self.left(node), self.right(node));
+        let size = 1 + self.subtree_size(&left) + self.subtree_size(&right);
+        let height = 1 + max(self.subtree_height(&left), self.subtree_height(&right));
+        self.set_size(node, size);
+        self.set_height(node, height);
+        if let Some(refresh) = self.refresh {
+            refresh(self, node);
+        }
+    }
+
+    pub(crate) fn subtree_height(&self, node: &Option<Link<K, V, B>>) -> i32 {
+        node.as_ref().map_or(0, |node| self.height(node))
+    }
+
+    pub(crate) fn subtree_size(&self, node: &Option<Link<K, V, B>>) -> usize {
+        node.as_ref().map_or(0, |node| self.size(node))
+    }
+
+    // Relinks every node into a tree as balanced as possible, in O(n). Each subtree then holds
+    // the same number of nodes on either side give or take one, which satisfies the AVL rules,
+    // and the red-black rules too when the deepest level is colored red and the rest black.
+    pub(crate) fn rebuild_balanced(&mut self, colored: bool) {
+        let mut nodes = Vec::with_capacity(self.len);
+        let mut iter = self.iter();
+        while let Some(node) = iter.next_node() {
+            nodes.push(node);
+        }
+        self.link_sorted(&nodes, colored);
+    }
+
+    // Fills an empty tree from entries in strictly ascending key order, in O(n), balanced like
+    // `rebuild_balanced` does. Fails if a key is not larger than the one before it, leaving
+    // the tree empty.
+    pub(crate) fn build_sorted(
+        &mut self,
+        entries: impl IntoIterator<Item = (K, V)>,
+        colored: bool,
+    ) -> Result<(), TreeError> {
+        let mut nodes: Vec<Link<K, V, B>> = Vec::new();
+        for (key, value) in entries {
+            let node = self.new_node(key, value);
+            let ascending = nodes
+                .last()
+                .is_none_or(|last| self.key(last) < self.key(&node));
+            nodes.push(node);
+            if !ascending {
+                let index = nodes.len() - 1;
+                for node in nodes {
+                    self.free_node(node);
+                }
+                return Err(TreeError::NotAscending { index });
+            }
+        }
+        self.len = nodes.len();
+        self.link_sorted(&nodes, colored);
+        Ok(())
+    }
+
+    fn link_sorted(&mut self, nodes: &[Link<K, V, B>], colored: bool) {
+        let red_depth = nodes.len().checked_ilog2().filter(|_| colored);
+        self.root = self.link_balanced(nodes, 0, red_depth);
+        if let Some(root) = self.root.clone() {
+            self.set_parent(&root, None);
+            // A lone root is the deepest level too, but has to stay black
+            if colored {
+                self.set_color(&root, Some(NodeColor::Black));
+            }
+        }
+    }
+
+    // Nodes are left without a color when there is no `red_depth`
+    fn link_balanced(
+        &mut self,
+        nodes: &[Link<K, V, B>],
+        depth: u32,
+        red_depth: Option<u32>,
+    ) -> Option<Link<K, V, B>> {
+        if nodes.is_empty() {
+            return None;
+        }
+        let middle = nodes.len() / 2;
+        let node = nodes[middle].clone();
+        let left = self.link_balanced(&nodes[..middle], depth + 1, red_depth);
+        let right = self.link_balanced(&nodes[middle + 1..], depth + 1, red_depth);
+        for child in [&left, &right].into_iter().flatten() {
+            self.set_parent(child, Some(node.clone()));
+        }
+        self.set_left(&node, left);
+        self.set_right(&node, right);
+        self.update_size(&node);
+        let color = red_depth.map(|red_depth| {
+            if red_depth == depth {
+                NodeColor::Red
+            } else {
+                NodeColor::Black
+            }
+        });
+        self.set_color(&node, color);
+        Some(node)
+    }
+
+    /// Returns the key at position `index` in ascending order.
+    pub fn select(&self, index: usize) -> Option<K>
+    where
+        K: Clone,
+    {
+        let node = self.select_node(index)?;
+        Some(self.key(&node).clone())
+    }
+
+    pub(crate) fn select_node(&self, mut index: usize) -> Option<Link<K, V, B>> {
+        let mut current_node = self.root.clone();
+        while let Some(node) = current_node {
+            let left_size = self.subtree_size(&self.left(&node));
+            if index < left_size {
+                current_node = self.left(&node);
+            } else if index == left_size {
+                return Some(node);
+            } else {
+                index -= left_size + 1;
+                current_node = self.right(&node);
+            }
+        }
+        None
+    }
+
+    /// Returns the number of keys that are smaller than `key`.
+    pub fn rank(&self, key: &K) -> usize {
+        let mut rank = 0;
+        let mut current_node = self.root.clone();
+        while let Some(node) = current_node {
+            if self.key(&node) < key {
+                rank += self.subtree_size(&self.left(&node)) + 1;
+                current_node = self.right(&node);
+            } else {
+                current_node = self.left(&node);
+            }
+        }
+        rank
+    }
+
+    // Checks the properties that both kinds of tree share at `node`, whose key has to lie
+    // strictly between `lower` and `upper`, and the parent links of its children
+    pub(crate) fn check_node(
+        &self,
+        node: &Link<K, V, B>,
+        lower: Option<&K>,
+        upper: Option<&K>,
+        violations: &mut Vec<InvariantViolation<K>>,
+    ) where
+        K: Clone,
+    {
+        let key = self.key(node);
+        if lower.is_some_and(|lower| key <= lower) || upper.is_some_and(|upper| key >= upper) {
+            violations.push(self.violation(node, Invariant::Order));
+        }
+        let size = 1 + self.subtree_size(&self.left(node)) + self.subtree_size(&self.right(node));
+        if self.size(node) != size {
+            violations.push(self.violation(node, Invariant::Size));
+        }
+        for child in [self.left(node), self.right(node)].into_iter().flatten() {
+            if self.parent(&child).as_ref() != Some(node) {
+                violations.push(self.violation(&child, Invariant::Parent));
+            }
+        }
+    }
+
+    // The root has no parent
+    pub(crate) fn check_root(&self, violations: &mut Vec<InvariantViolation<K>>)
+    where
+        K: Clone,
+    {
+        if let Some(root) = &self.root {
+            if self.parent(root).is_some() {
+                violations.push(self.violation(root, Invariant::Parent));
+            }
+        }
+    }
+
+    pub(crate) fn violation(
+        &self,
+        node: &Link<K, V, B>,
+        invariant: Invariant,
+    ) -> InvariantViolation<K>
+    where
+        K: Clone,
+    {
+        InvariantViolation {
+            key: self.key(node).clone(),
+            invariant,
+        }
     }
 
-    pub fn get_balance(node: &Tree) -> i32 {
-        let left_height = node.borrow().left.as_ref().map_or(0, |n| n.borrow().height);
-        let right_height = node
-            .borrow()
-            .right
-            .as_ref()
-            .map_or(0, |n| n.borrow().height);
+    pub(crate) fn get_balance(&self, node: &Link<K, V, B>) -> i32 {
+        let left_height = self.subtree_height(&self.left(node));
+        let right_height = self.subtree_height(&self.right(node));
         left_height - right_height
     }
 
     // Count Function
     pub fn count_leaves(&self) -> i32 {
-        Self::count_leaves_recursive(&self.root)
+        self.count_leaves_recursive(&self.root)
     }
 
     // Helper function to recursively count the leaves
-    fn count_leaves_recursive(node: &GenericTree) -> i32 {
+    fn count_leaves_recursive(&self, node: &Option<Link<K, V, B>>) -> i32 {
         match node {
             Some(node) => {
-                let node_borrowed = node.borrow();
-                if node_borrowed.left.is_none() && node_borrowed.right.is_none() {
+                let (left, right) = (self.left(node), self.right(node));
+                if left.is_none() && right.is_none() {
                     1 // This node is a leaf
                 } else {
                     // Recursively count the leaves in the left and right subtrees and sum them up
-                    Self::count_leaves_recursive(&node_borrowed.left)
-                        + Self::count_leaves_recursive(&node_borrowed.right)
+                    self.count_leaves_recursive(&left) + self.count_leaves_recursive(&right)
                 }
             }
             None => 0, // If the node is None, it's not a leaf
         }
     }
 
-    pub fn search(&self, key: i32) -> Option<Tree> {
+    /// Returns the node holding `key`. Outside the crate, `lower_bound` on the trees gives a
+    /// cursor at the key instead.
+    pub(crate) fn search(&self, key: &K) -> Option<Link<K, V, B>> {
         let mut current_node = self.root.clone();
         while let Some(node) = current_node {
-            let node_ref = node.borrow();
-            if node_ref.key == key {
-                return Some(node.clone());
-            } else if key < node_ref.key {
-                current_node = node_ref.left.clone();
+            let node_key = self.key(&node);
+            if node_key == key {
+                return Some(node);
+            } else if key < node_key {
+                current_node = self.left(&node);
             } else {
-                current_node = node_ref.right.clone();
+                current_node = self.right(&node);
             }
         }
         None
     }
 
-    pub fn print_structure(&self) {
+    pub fn print_structure(&self)
+    where
+        K: Display,
+    {
         self.print_helper(&self.root, 0, "Root: ");
     }
 
-    fn print_helper(&self, node: &GenericTree, space: usize, prefix: &str) {
-        if node.is_none() {
-            return;
-        }
+    fn print_helper(&self, node: &Option<Link<K, V, B>>, space: usize, prefix: &str)
+    where
+        K: Display,
+    {
+        let node = match node {
+            Some(node) => node,
+            None => return,
+        };
         let space = space + 10;
 
-        if let Some(ref right) = node.as_ref().unwrap().borrow().right {
-            self.print_helper(&Some(right.clone()), space, "R: ");
-        }
+        self.print_helper(&self.right(node), space, "R: ");
 
         for _ in 10..space {
             print!(" ");
         }
         // Modify this line to include the color of the node
-        let node_ref = node.as_ref().unwrap().borrow();
-        match node_ref.color {
+        let key = self.key(node);
+        match self.color(node) {
             Some(NodeColor::Red) => {
-                println!("{}{}", prefix.red(), node_ref.key.to_string().red())
+                println!("{}{}", prefix.red(), key.to_string().red())
             }
             Some(NodeColor::Black) => {
-                println!("{}{}", prefix.black(), node_ref.key.to_string().black())
+                println!("{}{}", prefix.black(), key.to_string().black())
             }
             None => {
-                println!("{}{}", prefix, node_ref.key.to_string())
+                println!("{}{}", prefix, key)
             }
         };
 
-        if let Some(ref left) = node_ref.left {
-            self.print_helper(&Some(left.clone()), space, "L: ");
+        self.print_helper(&self.left(node), space, "L: ");
+    }
+}
+
+// Node access for the balancing code. Links only ever come from this tree's own root and
+// nodes, which is what the `Storage` safety contract asks for, and every change to a node
+// borrows the tree mutably.
+impl<K, V, B: Backend> CommonTree<K, V, B> {
+    pub(crate) fn new_node(&mut self, key: K, value: V) -> Link<K, V, B> {
+        self.store.alloc(key, value)
+    }
+
+    pub(crate) fn free_node(&mut self, node: Link<K, V, B>) -> (K, V) {
+        unsafe { self.store.free(node) }
+    }
+
+    pub(crate) fn adopt(
+        &mut self,
+        other: &mut CommonTree<K, V, B>,
+        node: Option<Link<K, V, B>>,
+    ) -> Option<Link<K, V, B>> {
+        node.map(|node| unsafe { self.store.adopt(&mut other.store, node) })
+    }
+
+    pub(crate) fn left(&self, node: &Link<K, V, B>) -> Option<Link<K, V, B>> {
+        unsafe { self.store.left(node) }
+    }
+
+    pub(crate) fn right(&self, node: &Link<K, V, B>) -> Option<Link<K, V, B>> {
+        unsafe { self.store.right(node) }
+    }
+
+    pub(crate) fn parent(&self, node: &Link<K, V, B>) -> Option<Link<K, V, B>> {
+        unsafe { self.store.parent(node) }
+    }
+
+    pub(crate) fn set_left(&mut self, node: &Link<K, V, B>, child: Option<Link<K, V, B>>) {
+        unsafe { self.store.set_left(node, child) }
+    }
+
+    pub(crate) fn set_right(&mut self, node: &Link<K, V, B>, child: Option<Link<K, V, B>>) {
+        unsafe { self.store.set_right(node, child) }
+    }
+
+    pub(crate) fn set_parent(&mut self, node: &Link<K, V, B>, parent: Option<Link<K, V, B>>) {
+        unsafe { self.store.set_parent(node, parent) }
+    }
+
+    // Like `set_left`, but also points the child back at `node`
+    pub(crate) fn link_left(&mut self, node: &Link<K, V, B>, child: Option<Link<K, V, B>>) {
+        if let Some(child) = &child {
+            self.set_parent(child, Some(node.clone()));
+        }
+        self.set_left(node, child);
+    }
+
+    pub(crate) fn link_right(&mut self, node: &Link<K, V, B>, child: Option<Link<K, V, B>>) {
+        if let Some(child) = &child {
+            self.set_parent(child, Some(node.clone()));
+        }
+        self.set_right(node, child);
+    }
+
+    // Makes `root` the root, clearing a parent link left over from where it was before
+    pub(crate) fn set_root(&mut self, root: Option<Link<K, V, B>>) {
+        if let Some(root) = &root {
+            self.set_parent(root, None);
         }
+        self.root = root;
+    }
+
+    pub(crate) fn height(&self, node: &Link<K, V, B>) -> i32 {
+        unsafe { self.store.height(node) }
+    }
+
+    pub(crate) fn set_height(&mut self, node: &Link<K, V, B>, height: i32) {
+        unsafe { self.store.set_height(node, height) }
+    }
+
+    pub(crate) fn size(&self, node: &Link<K, V, B>) -> usize {
+        unsafe { self.store.size(node) }
+    }
+
+    pub(crate) fn set_size(&mut self, node: &Link<K, V, B>, size: usize) {
+        unsafe { self.store.set_size(node, size) }
+    }
+
+    pub(crate) fn color(&self, node: &Link<K, V, B>) -> Option<NodeColor> {
+        unsafe { self.store.color(node) }
+    }
+
+    pub(crate) fn set_color(&mut self, node: &Link<K, V, B>, color: Option<NodeColor>) {
+        unsafe { self.store.set_color(node, color) }
+    }
+
+    pub(crate) fn key(&self, node: &Link<K, V, B>) -> &K {
+        unsafe { self.store.key(node) }
+    }
+
+    pub(crate) fn value(&self, node: &Link<K, V, B>) -> &V {
+        unsafe { self.store.value(node) }
+    }
+
+    pub(crate) fn value_mut(&mut self, node: &Link<K, V, B>) -> &mut V {
+        unsafe { self.store.value_mut(node) }
     }
 }
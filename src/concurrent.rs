@@ -0,0 +1,99 @@
+use crate::ordered_set::OrderedSet;
+use std::ops::RangeBounds;
+use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// A tree that can be shared between threads, for example through an `Arc`. Any number of
+/// readers can search it at the same time, while writers wait for each other and for the
+/// readers.
+///
+/// The tree has to be `Send + Sync`, which trees on [`ArenaBackend`](crate::ArenaBackend)
+/// are whenever their keys are, e.g. `ConcurrentTree<AVLTree<i32, ArenaBackend>>`. Trees on
+/// the default backend share their nodes through `Rc` and stay on one thread.
+///
+/// # Panics
+///
+/// Every method panics if another thread panicked while it was changing the tree, since the
+/// tree may have been left half rebalanced.
+pub struct ConcurrentTree<T> {
+    tree: RwLock<T>,
+}
+
+impl<T: Default> Default for ConcurrentTree<T> {
+    fn default() -> Self {
+        ConcurrentTree::new(T::default())
+    }
+}
+
+impl<T> ConcurrentTree<T> {
+    pub fn new(tree: T) -> Self {
+        ConcurrentTree {
+            tree: RwLock::new(tree),
+        }
+    }
+
+    /// Locks the tree for reading, for queries that the methods below do not cover. Other
+    /// readers are not blocked while the guard is held.
+    pub fn read(&self) -> RwLockReadGuard<'_, T> {
+        self.tree.read().expect("Tree lock was poisoned")
+    }
+
+    /// Locks the tree for writing, so that several changes are seen by readers at once.
+    pub fn write(&self) -> RwLockWriteGuard<'_, T> {
+        self.tree.write().expect("Tree lock was poisoned")
+    }
+
+    pub fn into_inner(self) -> T {
+        self.tree.into_inner().expect("Tree lock was poisoned")
+    }
+
+    pub fn contains<K: Ord + Clone>(&self, key: &K) -> bool
+    where
+        T: OrderedSet<K>,
+    {
+        self.read().contains(key)
+    }
+
+    pub fn len<K: Ord + Clone>(&self) -> usize
+    where
+        T: OrderedSet<K>,
+    {
+        self.read().len()
+    }
+
+    pub fn is_empty<K: Ord + Clone>(&self) -> bool
+    where
+        T: OrderedSet<K>,
+    {
+        self.read().is_empty()
+    }
+
+    /// Returns the keys within `range` in ascending order. They are copied out, so that the
+    /// read lock is released before this returns.
+    pub fn range<K: Ord + Clone, R: RangeBounds<K>>(&self, range: R) -> Vec<K>
+    where
+        T: OrderedSet<K>,
+    {
+        let tree = self.read();
+        let keys = tree
+            .range(range.start_bound(), range.end_bound())
+            .cloned()
+            .collect();
+        keys
+    }
+
+    /// Inserts `key` unless it is already present, and returns whether it was inserted.
+    pub fn insert<K: Ord + Clone>(&self, key: K) -> bool
+    where
+        T: OrderedSet<K>,
+    {
+        self.write().insert(key)
+    }
+
+    /// Removes `key` and returns it, or `None` if it was not present.
+    pub fn remove<K: Ord + Clone>(&self, key: &K) -> Option<K>
+    where
+        T: OrderedSet<K>,
+    {
+        self.write().remove(key)
+    }
+}
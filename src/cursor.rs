@@ -0,0 +1,237 @@
+use crate::common_tree::CommonTree;
+use crate::entry::{Attach, Path};
+use crate::storage::{Backend, Link, RcBackend};
+use std::cmp::Ordering;
+use std::ops::Bound;
+
+// Unlinks a node, rebalances the tree the way its kind does and frees the node
+pub(crate) type Detach<K, V, B> = fn(&mut CommonTree<K, V, B>, Link<K, V, B>) -> (K, V);
+
+/// A position in a tree that moves between neighbouring keys by following parent links, so a
+/// whole scan takes O(1) amortized time per step instead of a search from the root each time.
+///
+/// Besides the keys there is one more position, past the largest key and before the smallest,
+/// where the cursor ends up after moving off either end. Moving on from there wraps around.
+pub struct Cursor<'a, K, V = (), B: Backend = RcBackend> {
+    tree: &'a CommonTree<K, V, B>,
+    current: Option<Link<K, V, B>>,
+}
+
+impl<K, V, B: Backend> Clone for Cursor<'_, K, V, B> {
+    fn clone(&self) -> Self {
+        Cursor {
+            tree: self.tree,
+            current: self.current.clone(),
+        }
+    }
+}
+
+impl<'a, K: Ord, V, B: Backend> Cursor<'a, K, V, B> {
+    pub(crate) fn new(tree: &'a CommonTree<K, V, B>, current: Option<Link<K, V, B>>) -> Self {
+        Cursor { tree, current }
+    }
+
+    /// Returns the key at the cursor, or `None` past the ends.
+    pub fn key(&self) -> Option<&'a K> {
+        let tree = self.tree;
+        self.current.as_ref().map(|node| tree.key(node))
+    }
+
+    pub fn value(&self) -> Option<&'a V> {
+        let tree = self.tree;
+        self.current.as_ref().map(|node| tree.value(node))
+    }
+
+    /// Returns the key that [`move_next`](Self::move_next) would move to.
+    pub fn peek_next(&self) -> Option<&'a K> {
+        let tree = self.tree;
+        next_of(tree, &self.current).map(|node| tree.key(&node))
+    }
+
+    /// Returns the key that [`move_prev`](Self::move_prev) would move to.
+    pub fn peek_prev(&self) -> Option<&'a K> {
+        let tree = self.tree;
+        prev_of(tree, &self.current).map(|node| tree.key(&node))
+    }
+
+    pub fn move_next(&mut self) {
+        self.current = next_of(self.tree, &self.current);
+    }
+
+    pub fn move_prev(&mut self) {
+        self.current = prev_of(self.tree, &self.current);
+    }
+
+    /// Moves to the smallest key within `bound`, or past the ends if there is none, with one
+    /// search from the root.
+    pub fn seek_lower_bound(&mut self, bound: Bound<&K>) {
+        self.current = self.tree.first_node_after(bound);
+    }
+}
+
+/// A [`Cursor`] that can also insert and remove keys next to its position, rebalancing the tree
+/// like [`insert`](crate::AVLTree::insert) and [`delete`](crate::AVLTree::delete) do.
+pub struct CursorMut<'a, K, V = (), B: Backend = RcBackend> {
+    tree: &'a mut CommonTree<K, V, B>,
+    current: Option<Link<K, V, B>>,
+    attach: Attach<K, V, B>,
+    detach: Detach<K, V, B>,
+}
+
+impl<'a, K: Ord, V, B: Backend> CursorMut<'a, K, V, B> {
+    pub(crate) fn new(
+        tree: &'a mut CommonTree<K, V, B>,
+        current: Option<Link<K, V, B>>,
+        attach: Attach<K, V, B>,
+        detach: Detach<K, V, B>,
+    ) -> Self {
+        CursorMut {
+            tree,
+            current,
+            attach,
+            detach,
+        }
+    }
+
+    /// Returns a read-only cursor at the same position, borrowing this one.
+    pub fn as_cursor(&self) -> Cursor<'_, K, V, B> {
+        Cursor::new(self.tree, self.current.clone())
+    }
+
+    /// Returns the key at the cursor, or `None` past the ends.
+    pub fn key(&self) -> Option<&K> {
+        self.current.as_ref().map(|node| self.tree.key(node))
+    }
+
+    pub fn value(&self) -> Option<&V> {
+        self.current.as_ref().map(|node| self.tree.value(node))
+    }
+
+    pub fn value_mut(&mut self) -> Option<&mut V> {
+        let node = self.current.as_ref()?;
+        Some(self.tree.value_mut(node))
+    }
+
+    /// Returns the key that [`move_next`](Self::move_next) would move to.
+    pub fn peek_next(&self) -> Option<&K> {
+        next_of(self.tree, &self.current).map(|node| self.tree.key(&node))
+    }
+
+    /// Returns the key that [`move_prev`](Self::move_prev) would move to.
+    pub fn peek_prev(&self) -> Option<&K> {
+        prev_of(self.tree, &self.current).map(|node| self.tree.key(&node))
+    }
+
+    pub fn move_next(&mut self) {
+        self.current = next_of(self.tree, &self.current);
+    }
+
+    pub fn move_prev(&mut self) {
+        self.current = prev_of(self.tree, &self.current);
+    }
+
+    /// Moves to the smallest key within `bound`, or past the ends if there is none, with one
+    /// search from the root.
+    pub fn seek_lower_bound(&mut self, bound: Bound<&K>) {
+        self.current = self.tree.first_node_after(bound);
+    }
+
+    /// Inserts `key` right after the cursor, or before the smallest key when the cursor is past
+    /// the ends, without moving the cursor.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `key` does not sort strictly between the key at the cursor and the next one.
+    pub fn insert_after(&mut self, key: K, value: V) {
+        assert!(
+            self.key().is_none_or(|current| *current < key)
+                && self.peek_next().is_none_or(|next| key < *next),
+            "Key inserted after the cursor must sort between its neighbours"
+        );
+        // The new key goes into the free slot between the cursor's node and the next one: the
+        // left child of the next node when it has none, and otherwise the right child of the
+        // cursor's node, as the next node then lies in the subtree on that side
+        let slot = match &self.current {
+            None => self.tree.first_node().map(|first| (first, Ordering::Less)),
+            Some(node) => Some(match self.tree.next_node(node) {
+                Some(next) if self.tree.left(&next).is_none() => (next, Ordering::Less),
+                _ => (node.clone(), Ordering::Greater),
+            }),
+        };
+        self.insert(slot, key, value);
+    }
+
+    /// Inserts `key` right before the cursor, or after the largest key when the cursor is past
+    /// the ends, without moving the cursor.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `key` does not sort strictly between the previous key and the key at the
+    /// cursor.
+    pub fn insert_before(&mut self, key: K, value: V) {
+        assert!(
+            self.peek_prev().is_none_or(|prev| *prev < key)
+                && self.key().is_none_or(|current| key < *current),
+            "Key inserted before the cursor must sort between its neighbours"
+        );
+        // Mirrors `insert_after`
+        let slot = match &self.current {
+            None => self.tree.last_node().map(|last| (last, Ordering::Greater)),
+            Some(node) => Some(match self.tree.prev_node(node) {
+                Some(prev) if self.tree.right(&prev).is_none() => (prev, Ordering::Greater),
+                _ => (node.clone(), Ordering::Less),
+            }),
+        };
+        self.insert(slot, key, value);
+    }
+
+    // Attaches a new node in the free child slot of `slot`, or as the root of an empty tree. The
+    // path that the tree rebalances along is found by climbing the parent links from there.
+    fn insert(&mut self, slot: Option<(Link<K, V, B>, Ordering)>, key: K, value: V) {
+        let mut path: Path<K, V, B> = Vec::new();
+        if let Some((mut node, ordering)) = slot {
+            path.push((node.clone(), ordering));
+            while let Some(parent) = self.tree.parent(&node) {
+                let ordering = if self.tree.left(&parent).as_ref() == Some(&node) {
+                    Ordering::Less
+                } else {
+                    Ordering::Greater
+                };
+                path.push((parent.clone(), ordering));
+                node = parent;
+            }
+            path.reverse();
+        }
+        let node = self.tree.new_node(key, value);
+        (self.attach)(self.tree, path, node);
+    }
+
+    /// Removes the key at the cursor and moves to the next one. Returns `None` and does nothing
+    /// past the ends.
+    pub fn remove_current(&mut self) -> Option<(K, V)> {
+        let node = self.current.take()?;
+        // Rebalancing moves nodes around but keeps every node other than the removed one alive
+        self.current = self.tree.next_node(&node);
+        Some((self.detach)(self.tree, node))
+    }
+}
+
+fn next_of<K: Ord, V, B: Backend>(
+    tree: &CommonTree<K, V, B>,
+    current: &Option<Link<K, V, B>>,
+) -> Option<Link<K, V, B>> {
+    match current {
+        Some(node) => tree.next_node(node),
+        None => tree.first_node(),
+    }
+}
+
+fn prev_of<K: Ord, V, B: Backend>(
+    tree: &CommonTree<K, V, B>,
+    current: &Option<Link<K, V, B>>,
+) -> Option<Link<K, V, B>> {
+    match current {
+        Some(node) => tree.prev_node(node),
+        None => tree.last_node(),
+    }
+}
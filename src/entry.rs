@@ -0,0 +1,131 @@
+use crate::common_tree::CommonTree;
+use crate::storage::{Backend, Link, RcBackend};
+use std::cmp::Ordering;
+
+// Nodes passed on the way down from the root, each with how the searched key compares to it
+pub(crate) type Path<K, V, B> = Vec<(Link<K, V, B>, Ordering)>;
+
+// Links a new node below the end of a path and rebalances the tree the way its kind does
+pub(crate) type Attach<K, V, B> = fn(&mut CommonTree<K, V, B>, Path<K, V, B>, Link<K, V, B>);
+
+/// A view into a single key of a tree, which is either present or missing.
+pub enum Entry<'a, K, V = (), B: Backend = RcBackend> {
+    Vacant(VacantEntry<'a, K, V, B>),
+    Occupied(OccupiedEntry<'a, K, V, B>),
+}
+
+/// A key that is missing from the tree. The path the search took is kept, so that inserting the
+/// key does not search the tree again.
+pub struct VacantEntry<'a, K, V = (), B: Backend = RcBackend> {
+    tree: &'a mut CommonTree<K, V, B>,
+    key: K,
+    path: Path<K, V, B>,
+    attach: Attach<K, V, B>,
+}
+
+/// A key that is present in the tree.
+pub struct OccupiedEntry<'a, K, V = (), B: Backend = RcBackend> {
+    tree: &'a mut CommonTree<K, V, B>,
+    node: Link<K, V, B>,
+}
+
+impl<'a, K: Ord, V, B: Backend> Entry<'a, K, V, B> {
+    pub(crate) fn new(tree: &'a mut CommonTree<K, V, B>, key: K, attach: Attach<K, V, B>) -> Self {
+        let mut path = Vec::new();
+        let mut current = tree.root.clone();
+        while let Some(node) = current {
+            let ordering = key.cmp(tree.key(&node));
+            current = match ordering {
+                Ordering::Less => tree.left(&node),
+                Ordering::Greater => tree.right(&node),
+                Ordering::Equal => return Entry::Occupied(OccupiedEntry { tree, node }),
+            };
+            path.push((node, ordering));
+        }
+        Entry::Vacant(VacantEntry {
+            tree,
+            key,
+            path,
+            attach,
+        })
+    }
+
+    pub fn key(&self) -> &K {
+        match self {
+            Entry::Vacant(entry) => entry.key(),
+            Entry::Occupied(entry) => entry.key(),
+        }
+    }
+
+    /// Inserts `default` if the key is missing, and returns the value of the key.
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        self.or_insert_with(|| default)
+    }
+
+    /// Inserts the result of `default` if the key is missing, and returns the value of the key.
+    pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V {
+        match self {
+            Entry::Vacant(entry) => entry.insert(default()),
+            Entry::Occupied(entry) => entry.into_mut(),
+        }
+    }
+
+    pub fn or_default(self) -> &'a mut V
+    where
+        V: Default,
+    {
+        self.or_insert_with(V::default)
+    }
+
+    /// Runs `modify` on the value if the key is present.
+    pub fn and_modify<F: FnOnce(&mut V)>(self, modify: F) -> Self {
+        match self {
+            Entry::Occupied(mut entry) => {
+                modify(entry.get_mut());
+                Entry::Occupied(entry)
+            }
+            Entry::Vacant(entry) => Entry::Vacant(entry),
+        }
+    }
+}
+
+impl<'a, K: Ord, V, B: Backend> VacantEntry<'a, K, V, B> {
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    pub fn into_key(self) -> K {
+        self.key
+    }
+
+    /// Inserts the key with `value` and returns a reference to the value.
+    pub fn insert(self, value: V) -> &'a mut V {
+        let tree = self.tree;
+        let node = tree.new_node(self.key, value);
+        (self.attach)(tree, self.path, node.clone());
+        tree.value_mut(&node)
+    }
+}
+
+impl<'a, K: Ord, V, B: Backend> OccupiedEntry<'a, K, V, B> {
+    pub fn key(&self) -> &K {
+        self.tree.key(&self.node)
+    }
+
+    pub fn get(&self) -> &V {
+        self.tree.value(&self.node)
+    }
+
+    pub fn get_mut(&mut self) -> &mut V {
+        self.tree.value_mut(&self.node)
+    }
+
+    pub fn into_mut(self) -> &'a mut V {
+        self.tree.value_mut(&self.node)
+    }
+
+    /// Replaces the value and returns the old one.
+    pub fn insert(&mut self, value: V) -> V {
+        std::mem::replace(self.get_mut(), value)
+    }
+}
@@ -0,0 +1,84 @@
+use std::error::Error;
+use std::fmt;
+
+/// Reasons an operation on a tree can fail.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TreeError {
+    /// A key given to `from_sorted_iter` was not larger than the one before it. `index` is the
+    /// position of that key in the input.
+    NotAscending { index: usize },
+    /// An interval given to an `IntervalTree` did not start before it ended.
+    EmptyInterval,
+}
+
+impl fmt::Display for TreeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TreeError::NotAscending { index } => write!(
+                f,
+                "key at index {} is not larger than the key before it",
+                index
+            ),
+            TreeError::EmptyInterval => write!(f, "interval does not start before it ends"),
+        }
+    }
+}
+
+impl Error for TreeError {}
+
+/// A property of a balanced tree, as checked by `validate`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Invariant {
+    /// Every key is larger than the keys in its left subtree and smaller than the keys in its
+    /// right subtree.
+    Order,
+    /// The cached subtree size of a node counts the nodes below it.
+    Size,
+    /// The cached height of a node is the height of its subtree.
+    Height,
+    /// The subtrees of an AVL node differ in height by at most one.
+    Balance,
+    /// Every node of a red-black tree is either red or black.
+    Color,
+    /// A red node has no red child. The violation names the child.
+    RedRed,
+    /// Both subtrees of a red-black node have the same number of black nodes on every path.
+    BlackHeight,
+    /// The root of a red-black tree is black.
+    BlackRoot,
+    /// The parent link of a node points at the node it hangs from. The violation names the
+    /// child.
+    Parent,
+}
+
+impl fmt::Display for Invariant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let description = match self {
+            Invariant::Order => "key is out of order",
+            Invariant::Size => "cached subtree size is wrong",
+            Invariant::Height => "cached height is wrong",
+            Invariant::Balance => "subtree heights differ by more than one",
+            Invariant::Color => "node has no color",
+            Invariant::RedRed => "red node has a red parent",
+            Invariant::BlackHeight => "subtrees have different black heights",
+            Invariant::BlackRoot => "root is not black",
+            Invariant::Parent => "parent link is wrong",
+        };
+        f.write_str(description)
+    }
+}
+
+/// A broken [`Invariant`] at the node holding `key`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvariantViolation<K> {
+    pub key: K,
+    pub invariant: Invariant,
+}
+
+impl<K: fmt::Debug> fmt::Display for InvariantViolation<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "node {:?}: {}", self.key, self.invariant)
+    }
+}
+
+impl<K: fmt::Debug> Error for InvariantViolation<K> {}
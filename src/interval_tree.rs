@@ -0,0 +1,230 @@
+use crate::augment::{self, Augment, Summarized};
+use crate::common_tree::CommonTree;
+use crate::error::TreeError;
+use crate::iter::Iter;
+use crate::rb_tree::RedBlackTree;
+use crate::storage::{Backend, Link, RcBackend};
+use std::iter::FusedIterator;
+use std::ops::{Bound, Range};
+
+/// A set of half-open intervals `[start, end)`, ordered by start and then by end, that finds
+/// the intervals overlapping a query without looking at the others.
+///
+/// The intervals are kept in a red-black tree augmented with the largest end in each subtree,
+/// which the rotations and fixups of [`RedBlackTree`] keep up to date.
+pub struct IntervalTree<T: Ord + Clone, B: Backend = RcBackend> {
+    tree: Intervals<T, B>,
+}
+
+// Summarizes a subtree by the largest end of its intervals
+struct MaxEnd;
+
+impl<T: Ord + Clone> Augment<(T, T), ()> for MaxEnd {
+    type Summary = Option<T>;
+
+    fn identity() -> Option<T> {
+        None
+    }
+
+    fn measure((_, end): &(T, T), _: &()) -> Option<T> {
+        Some(end.clone())
+    }
+
+    fn combine(left: &Option<T>, right: &Option<T>) -> Option<T> {
+        left.clone().max(right.clone())
+    }
+}
+
+// Each node is keyed by its interval and summarized by the largest end in its subtree
+type Intervals<T, B> = CommonTree<(T, T), Summarized<(T, T), (), MaxEnd>, B>;
+type Node<T, B> = Link<(T, T), Summarized<(T, T), (), MaxEnd>, B>;
+
+impl<T: Ord + Clone, B: Backend> Default for IntervalTree<T, B> {
+    fn default() -> Self {
+        IntervalTree {
+            tree: augment::tree::<(T, T), (), MaxEnd, B>(),
+        }
+    }
+}
+
+impl<T: Ord + Clone> IntervalTree<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<T: Ord + Clone, B: Backend> IntervalTree<T, B> {
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    /// Iterates over the intervals as `(start, end)` pairs in ascending order.
+    pub fn iter(&self) -> Iter<'_, (T, T), ((), Option<T>), B> {
+        self.tree.iter()
+    }
+
+    pub fn contains(&self, interval: &(T, T)) -> bool {
+        self.tree.search(interval).is_some()
+    }
+
+    /// Inserts the interval `[start, end)` unless it is already present, and returns whether
+    /// it was inserted.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TreeError::EmptyInterval`] if `start` is not smaller than `end`.
+    pub fn insert(&mut self, interval: (T, T)) -> Result<bool, TreeError> {
+        if interval.0 >= interval.1 {
+            return Err(TreeError::EmptyInterval);
+        }
+        let replaced = augment::insert::<(T, T), (), MaxEnd, B>(
+            &mut self.tree,
+            interval,
+            (),
+            RedBlackTree::attach,
+        );
+        Ok(replaced.is_none())
+    }
+
+    /// Removes the interval and returns it, or `None` if it was not present.
+    pub fn remove(&mut self, interval: &(T, T)) -> Option<(T, T)> {
+        let node = self.tree.search(interval)?;
+        Some(RedBlackTree::delete_node(&mut self.tree, node).0)
+    }
+
+    /// Iterates in ascending order over the intervals that share a point with `query`, in
+    /// O(log n) per interval found. An empty query overlaps nothing.
+    pub fn overlapping(&self, query: Range<T>) -> Overlapping<'_, T, B> {
+        if query.start >= query.end {
+            return Overlapping::empty(&self.tree, query.start);
+        }
+        Overlapping::new(&self.tree, query.start, Bound::Excluded(query.end))
+    }
+
+    /// Iterates in ascending order over the intervals that contain `point`.
+    pub fn stab(&self, point: T) -> Overlapping<'_, T, B> {
+        Overlapping::new(&self.tree, point.clone(), Bound::Included(point))
+    }
+
+    /// Returns whether any interval shares a point with `query`, in O(log n).
+    pub fn any_overlap(&self, query: Range<T>) -> bool {
+        if query.start >= query.end {
+            return false;
+        }
+        let tree = &self.tree;
+        let mut current = tree.root.clone();
+        while let Some(node) = current {
+            let (start, end) = tree.key(&node);
+            if *start < query.end && query.start < *end {
+                return true;
+            }
+            // An overlap on the left exists if any does, unless every interval there ends too
+            // early
+            current = match tree.left(&node) {
+                Some(left) if ends_after(tree, &left, &query.start) => Some(left),
+                _ => tree.right(&node),
+            };
+        }
+        false
+    }
+}
+
+// Whether some interval below `node` ends after `point`
+fn ends_after<T: Ord + Clone, B: Backend>(
+    tree: &Intervals<T, B>,
+    node: &Node<T, B>,
+    point: &T,
+) -> bool {
+    tree.value(node)
+        .1
+        .as_ref()
+        .is_some_and(|max_end| max_end > point)
+}
+
+impl<'a, T: Ord + Clone, B: Backend> IntoIterator for &'a IntervalTree<T, B> {
+    type Item = &'a (T, T);
+    type IntoIter = Iter<'a, (T, T), ((), Option<T>), B>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// In-order iterator over the intervals of an [`IntervalTree`] that overlap a query. Subtrees
+/// whose largest end is too small, and every interval after the first one that starts too
+/// late, are never visited.
+pub struct Overlapping<'a, T: Ord + Clone, B: Backend = RcBackend> {
+    tree: &'a Intervals<T, B>,
+    // Nodes whose left subtree has been searched but which have not been looked at themselves
+    stack: Vec<Node<T, B>>,
+    // An interval overlaps if it ends after `after` and starts within `before`
+    after: T,
+    before: Bound<T>,
+}
+
+impl<'a, T: Ord + Clone, B: Backend> Overlapping<'a, T, B> {
+    fn new(tree: &'a Intervals<T, B>, after: T, before: Bound<T>) -> Self {
+        let mut iter = Overlapping {
+            tree,
+            stack: Vec::new(),
+            after,
+            before,
+        };
+        iter.push_left(tree.root.clone());
+        iter
+    }
+
+    fn empty(tree: &'a Intervals<T, B>, after: T) -> Self {
+        Overlapping {
+            tree,
+            stack: Vec::new(),
+            after,
+            before: Bound::Unbounded,
+        }
+    }
+
+    fn push_left(&mut self, mut node: Option<Node<T, B>>) {
+        while let Some(current) = node {
+            if !ends_after(self.tree, &current, &self.after) {
+                break;
+            }
+            node = self.tree.left(&current);
+            self.stack.push(current);
+        }
+    }
+
+    fn starts_in_time(&self, start: &T) -> bool {
+        match &self.before {
+            Bound::Included(before) => start <= before,
+            Bound::Excluded(before) => start < before,
+            Bound::Unbounded => true,
+        }
+    }
+}
+
+impl<'a, T: Ord + Clone, B: Backend> Iterator for Overlapping<'a, T, B> {
+    type Item = &'a (T, T);
+
+    fn next(&mut self) -> Option<&'a (T, T)> {
+        let tree = self.tree;
+        while let Some(node) = self.stack.pop() {
+            let interval = tree.key(&node);
+            // Every interval after this one starts at least as late
+            if !self.starts_in_time(&interval.0) {
+                self.stack.clear();
+                return None;
+            }
+            self.push_left(tree.right(&node));
+            if interval.1 > self.after {
+                return Some(interval);
+            }
+        }
+        None
+    }
+}
+
+impl<T: Ord + Clone, B: Backend> FusedIterator for Overlapping<'_, T, B> {}
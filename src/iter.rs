@@ -0,0 +1,446 @@
+use crate::common_tree::CommonTree;
+use crate::storage::{Backend, Link, RcBackend};
+use std::iter::FusedIterator;
+use std::ops::{Bound, RangeBounds};
+
+/// In-order iterator over the keys of a tree. Only the paths to the next key from either end
+/// are kept, so creating and advancing it takes O(log n) space.
+pub struct Iter<'a, K, V = (), B: Backend = RcBackend> {
+    tree: &'a CommonTree<K, V, B>,
+    // Nodes whose key has not been yielded yet, with the next one from each end on top
+    front: Vec<Link<K, V, B>>,
+    back: Vec<Link<K, V, B>>,
+    len: usize,
+}
+
+impl<'a, K, V, B: Backend> Iter<'a, K, V, B> {
+    pub(crate) fn new(tree: &'a CommonTree<K, V, B>) -> Self {
+        let mut iter = Iter {
+            tree,
+            front: Vec::new(),
+            back: Vec::new(),
+            len: tree.len,
+        };
+        iter.push_left(tree.root.clone());
+        iter.push_right(tree.root.clone());
+        iter
+    }
+
+    fn push_left(&mut self, mut node: Option<Link<K, V, B>>) {
+        while let Some(current) = node {
+            node = self.tree.left(&current);
+            self.front.push(current);
+        }
+    }
+
+    fn push_right(&mut self, mut node: Option<Link<K, V, B>>) {
+        while let Some(current) = node {
+            node = self.tree.right(&current);
+            self.back.push(current);
+        }
+    }
+
+    // Both stacks hold every remaining node between them, so `len` is what stops the two ends
+    // from running past each other
+    pub(crate) fn next_node(&mut self) -> Option<Link<K, V, B>> {
+        if self.len == 0 {
+            return None;
+        }
+        let node = self.front.pop()?;
+        self.push_left(self.tree.right(&node));
+        self.len -= 1;
+        Some(node)
+    }
+
+    pub(crate) fn next_back_node(&mut self) -> Option<Link<K, V, B>> {
+        if self.len == 0 {
+            return None;
+        }
+        let node = self.back.pop()?;
+        self.push_right(self.tree.left(&node));
+        self.len -= 1;
+        Some(node)
+    }
+}
+
+impl<'a, K, V, B: Backend> Iterator for Iter<'a, K, V, B> {
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<&'a K> {
+        let node = self.next_node()?;
+        let tree = self.tree;
+        Some(tree.key(&node))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, K, V, B: Backend> DoubleEndedIterator for Iter<'a, K, V, B> {
+    fn next_back(&mut self) -> Option<&'a K> {
+        let node = self.next_back_node()?;
+        let tree = self.tree;
+        Some(tree.key(&node))
+    }
+}
+
+impl<K, V, B: Backend> ExactSizeIterator for Iter<'_, K, V, B> {}
+
+impl<K, V, B: Backend> FusedIterator for Iter<'_, K, V, B> {}
+
+/// In-order iterator over the keys of a tree that fall within a range.
+pub struct Range<'a, K, V = (), B: Backend = RcBackend> {
+    tree: &'a CommonTree<K, V, B>,
+    front: Vec<Link<K, V, B>>,
+    back: Vec<Link<K, V, B>>,
+    // Set once the two ends have met
+    done: bool,
+}
+
+pub(crate) fn after_start<K: Ord>(start: Bound<&K>, key: &K) -> bool {
+    match start {
+        Bound::Included(start) => key >= start,
+        Bound::Excluded(start) => key > start,
+        Bound::Unbounded => true,
+    }
+}
+
+pub(crate) fn before_end<K: Ord>(end: Bound<&K>, key: &K) -> bool {
+    match end {
+        Bound::Included(end) => key <= end,
+        Bound::Excluded(end) => key < end,
+        Bound::Unbounded => true,
+    }
+}
+
+impl<'a, K: Ord, V, B: Backend> Range<'a, K, V, B> {
+    pub(crate) fn new<R: RangeBounds<K>>(tree: &'a CommonTree<K, V, B>, range: R) -> Self {
+        let (start, end) = (range.start_bound(), range.end_bound());
+        match (start, end) {
+            (Bound::Excluded(start), Bound::Excluded(end)) if start == end => {
+                panic!("range start and end are equal and excluded in Range")
+            }
+            (
+                Bound::Included(start) | Bound::Excluded(start),
+                Bound::Included(end) | Bound::Excluded(end),
+            ) if start > end => panic!("range start is greater than range end in Range"),
+            _ => {}
+        }
+
+        // Each end only keeps the nodes on its search path that lie inside the range, which
+        // leaves the first and last keys in range on top
+        let mut front = Vec::new();
+        let mut node = tree.root.clone();
+        while let Some(current) = node {
+            if after_start(start, tree.key(&current)) {
+                node = tree.left(&current);
+                front.push(current);
+            } else {
+                node = tree.right(&current);
+            }
+        }
+
+        let mut back = Vec::new();
+        let mut node = tree.root.clone();
+        while let Some(current) = node {
+            if before_end(end, tree.key(&current)) {
+                node = tree.right(&current);
+                back.push(current);
+            } else {
+                node = tree.left(&current);
+            }
+        }
+
+        let done = match (front.last(), back.last()) {
+            (Some(first), Some(last)) => tree.key(first) > tree.key(last),
+            _ => true,
+        };
+        Range {
+            tree,
+            front,
+            back,
+            done,
+        }
+    }
+}
+
+impl<'a, K, V, B: Backend> Range<'a, K, V, B> {
+    pub(crate) fn next_node(&mut self) -> Option<Link<K, V, B>> {
+        if self.done {
+            return None;
+        }
+        let node = self.front.pop()?;
+        self.done = self.back.last() == Some(&node);
+        let mut child = self.tree.right(&node);
+        while let Some(current) = child {
+            child = self.tree.left(&current);
+            self.front.push(current);
+        }
+        Some(node)
+    }
+
+    pub(crate) fn next_back_node(&mut self) -> Option<Link<K, V, B>> {
+        if self.done {
+            return None;
+        }
+        let node = self.back.pop()?;
+        self.done = self.front.last() == Some(&node);
+        let mut child = self.tree.left(&node);
+        while let Some(current) = child {
+            child = self.tree.right(&current);
+            self.back.push(current);
+        }
+        Some(node)
+    }
+}
+
+impl<'a, K, V, B: Backend> Iterator for Range<'a, K, V, B> {
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<&'a K> {
+        let node = self.next_node()?;
+        let tree = self.tree;
+        Some(tree.key(&node))
+    }
+}
+
+impl<'a, K, V, B: Backend> DoubleEndedIterator for Range<'a, K, V, B> {
+    fn next_back(&mut self) -> Option<&'a K> {
+        let node = self.next_back_node()?;
+        let tree = self.tree;
+        Some(tree.key(&node))
+    }
+}
+
+impl<K, V, B: Backend> FusedIterator for Range<'_, K, V, B> {}
+
+/// Owning in-order iterator over the keys of a tree. Nodes are unlinked and freed as they are
+/// yielded, and whatever is left is dropped with the iterator.
+pub struct IntoIter<K, V = (), B: Backend = RcBackend> {
+    tree: CommonTree<K, V, B>,
+    // Paths from the root to the smallest and to the largest remaining node
+    front: Vec<Link<K, V, B>>,
+    back: Vec<Link<K, V, B>>,
+}
+
+impl<K, V, B: Backend> IntoIter<K, V, B> {
+    pub(crate) fn new(tree: CommonTree<K, V, B>) -> Self {
+        let root = tree.root.clone();
+        let mut iter = IntoIter {
+            tree,
+            front: Vec::new(),
+            back: Vec::new(),
+        };
+        iter.push_left(root.clone());
+        iter.push_right(root);
+        iter
+    }
+
+    fn push_left(&mut self, mut node: Option<Link<K, V, B>>) {
+        while let Some(current) = node {
+            node = self.tree.left(&current);
+            self.front.push(current);
+        }
+    }
+
+    fn push_right(&mut self, mut node: Option<Link<K, V, B>>) {
+        while let Some(current) = node {
+            node = self.tree.right(&current);
+            self.back.push(current);
+        }
+    }
+
+    // The smallest node has no left child, so its right subtree takes its place. Every node on
+    // the front path but the root is a left child and every node on the back path but the root
+    // is a right child, so the root is the only node the other path can hold.
+    pub(crate) fn next_entry(&mut self) -> Option<(K, V)> {
+        let node = self.front.pop()?;
+        let right = self.tree.right(&node);
+        self.tree.set_right(&node, None);
+        match self.front.last().cloned() {
+            Some(parent) => self.tree.set_left(&parent, right.clone()),
+            None => {
+                self.tree.root = right.clone();
+                self.back.remove(0);
+            }
+        }
+        self.push_left(right);
+        self.tree.len -= 1;
+        Some(self.tree.free_node(node))
+    }
+
+    pub(crate) fn next_back_entry(&mut self) -> Option<(K, V)> {
+        let node = self.back.pop()?;
+        let left = self.tree.left(&node);
+        self.tree.set_left(&node, None);
+        match self.back.last().cloned() {
+            Some(parent) => self.tree.set_right(&parent, left.clone()),
+            None => {
+                self.tree.root = left.clone();
+                self.front.remove(0);
+            }
+        }
+        self.push_right(left);
+        self.tree.len -= 1;
+        Some(self.tree.free_node(node))
+    }
+}
+
+impl<K, B: Backend> Iterator for IntoIter<K, (), B> {
+    type Item = K;
+
+    fn next(&mut self) -> Option<K> {
+        self.next_entry().map(|(key, _)| key)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.tree.len, Some(self.tree.len))
+    }
+}
+
+impl<K, B: Backend> DoubleEndedIterator for IntoIter<K, (), B> {
+    fn next_back(&mut self) -> Option<K> {
+        self.next_back_entry().map(|(key, _)| key)
+    }
+}
+
+impl<K, B: Backend> ExactSizeIterator for IntoIter<K, (), B> {}
+
+impl<K, B: Backend> FusedIterator for IntoIter<K, (), B> {}
+
+/// In-order iterator over the keys of a multiset, which yields every key as many times as it
+/// is held.
+pub struct MultiIter<'a, K, B: Backend = RcBackend> {
+    iter: Iter<'a, K, usize, B>,
+    // Keys taken from either end that still have copies left to yield
+    front: Option<(&'a K, usize)>,
+    back: Option<(&'a K, usize)>,
+    len: usize,
+}
+
+impl<'a, K, B: Backend> MultiIter<'a, K, B> {
+    pub(crate) fn new(tree: &'a CommonTree<K, usize, B>, len: usize) -> Self {
+        MultiIter {
+            iter: Iter::new(tree),
+            front: None,
+            back: None,
+            len,
+        }
+    }
+
+    fn entry(&self, node: Link<K, usize, B>) -> (&'a K, usize) {
+        let tree = self.iter.tree;
+        (tree.key(&node), *tree.value(&node))
+    }
+}
+
+// Yields one copy from a key with copies left, and clears it once they run out
+fn take_copy<T: Copy>(slot: &mut Option<(T, usize)>) -> Option<T> {
+    let (key, count) = slot.as_mut()?;
+    let key = *key;
+    *count -= 1;
+    if *count == 0 {
+        *slot = None;
+    }
+    Some(key)
+}
+
+impl<'a, K, B: Backend> Iterator for MultiIter<'a, K, B> {
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<&'a K> {
+        if self.front.is_none() {
+            self.front = match self.iter.next_node() {
+                Some(node) => Some(self.entry(node)),
+                None => self.back.take(),
+            };
+        }
+        let key = take_copy(&mut self.front)?;
+        self.len -= 1;
+        Some(key)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, K, B: Backend> DoubleEndedIterator for MultiIter<'a, K, B> {
+    fn next_back(&mut self) -> Option<&'a K> {
+        if self.back.is_none() {
+            self.back = match self.iter.next_back_node() {
+                Some(node) => Some(self.entry(node)),
+                None => self.front.take(),
+            };
+        }
+        let key = take_copy(&mut self.back)?;
+        self.len -= 1;
+        Some(key)
+    }
+}
+
+impl<K, B: Backend> ExactSizeIterator for MultiIter<'_, K, B> {}
+
+impl<K, B: Backend> FusedIterator for MultiIter<'_, K, B> {}
+
+/// Owning in-order iterator over the keys of a multiset. Every key is cloned for all of its
+/// copies but the last, which is moved out.
+pub struct MultiIntoIter<K, B: Backend = RcBackend> {
+    iter: IntoIter<K, usize, B>,
+    front: Option<(K, usize)>,
+    back: Option<(K, usize)>,
+    len: usize,
+}
+
+impl<K, B: Backend> MultiIntoIter<K, B> {
+    pub(crate) fn new(tree: CommonTree<K, usize, B>, len: usize) -> Self {
+        MultiIntoIter {
+            iter: IntoIter::new(tree),
+            front: None,
+            back: None,
+            len,
+        }
+    }
+}
+
+fn take_owned<K: Clone>(slot: &mut Option<(K, usize)>) -> Option<K> {
+    let (key, count) = slot.as_mut()?;
+    *count -= 1;
+    if *count > 0 {
+        return Some(key.clone());
+    }
+    slot.take().map(|(key, _)| key)
+}
+
+impl<K: Clone, B: Backend> Iterator for MultiIntoIter<K, B> {
+    type Item = K;
+
+    fn next(&mut self) -> Option<K> {
+        if self.front.is_none() {
+            self.front = self.iter.next_entry().or_else(|| self.back.take());
+        }
+        let key = take_owned(&mut self.front)?;
+        self.len -= 1;
+        Some(key)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<K: Clone, B: Backend> DoubleEndedIterator for MultiIntoIter<K, B> {
+    fn next_back(&mut self) -> Option<K> {
+        if self.back.is_none() {
+            self.back = self.iter.next_back_entry().or_else(|| self.front.take());
+        }
+        let key = take_owned(&mut self.back)?;
+        self.len -= 1;
+        Some(key)
+    }
+}
+
+impl<K: Clone, B: Backend> ExactSizeIterator for MultiIntoIter<K, B> {}
+
+impl<K: Clone, B: Backend> FusedIterator for MultiIntoIter<K, B> {}
@@ -1,7 +1,32 @@
+mod arena;
+pub mod augment;
 pub mod avl_tree;
-pub mod common_tree;
+mod common_tree;
+pub mod concurrent;
+pub mod cursor;
+pub mod entry;
+pub mod error;
+pub mod interval_tree;
+pub mod iter;
+pub mod node;
+pub mod ordered_set;
+pub mod persistent;
 pub mod rb_tree;
+pub mod set_ops;
+mod storage;
+pub mod testing;
 
-pub use avl_tree::AVLTree;
-pub use common_tree::CommonTree;
-pub use rb_tree::RedBlackTree;
+pub use arena::ArenaBackend;
+pub use augment::Augment;
+pub use avl_tree::{AVLAugmentedMap, AVLMap, AVLMultiset, AVLTree};
+pub use common_tree::NodeColor;
+pub use concurrent::ConcurrentTree;
+pub use cursor::{Cursor, CursorMut};
+pub use entry::Entry;
+pub use error::{Invariant, InvariantViolation, TreeError};
+pub use interval_tree::IntervalTree;
+pub use node::NodeRef;
+pub use ordered_set::OrderedSet;
+pub use persistent::{PersistentAVLTree, PersistentRedBlackTree};
+pub use rb_tree::{RedBlackAugmentedMap, RedBlackMap, RedBlackMultiset, RedBlackTree};
+pub use storage::{Backend, RcBackend};
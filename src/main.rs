@@ -1,7 +1,4 @@
-mod avl_tree;
-mod common_tree;
 mod cli;
-mod rb_tree;
 
 fn main() {
     cli::user_input_display();
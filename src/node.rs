@@ -0,0 +1,59 @@
+use crate::common_tree::{CommonTree, NodeColor};
+use crate::storage::{Backend, Link, RcBackend};
+
+/// A read-only view of one node, returned by `root()` on the trees, for walking and drawing
+/// the shape of a tree without reaching the storage behind it.
+pub struct NodeRef<'a, K, V = (), B: Backend = RcBackend> {
+    tree: &'a CommonTree<K, V, B>,
+    node: Link<K, V, B>,
+}
+
+impl<K, V, B: Backend> Clone for NodeRef<'_, K, V, B> {
+    fn clone(&self) -> Self {
+        NodeRef {
+            tree: self.tree,
+            node: self.node.clone(),
+        }
+    }
+}
+
+impl<'a, K: Ord, V, B: Backend> NodeRef<'a, K, V, B> {
+    pub(crate) fn new(tree: &'a CommonTree<K, V, B>, node: Link<K, V, B>) -> Self {
+        NodeRef { tree, node }
+    }
+
+    pub(crate) fn root(tree: &'a CommonTree<K, V, B>) -> Option<Self> {
+        tree.root.clone().map(|node| NodeRef::new(tree, node))
+    }
+
+    pub fn key(&self) -> &'a K {
+        self.tree.key(&self.node)
+    }
+
+    pub fn value(&self) -> &'a V {
+        self.tree.value(&self.node)
+    }
+
+    /// Returns the colour of the node in a red-black tree, or `None` in an AVL tree.
+    pub fn color(&self) -> Option<NodeColor> {
+        self.tree.color(&self.node)
+    }
+
+    /// Returns the number of nodes on the longest path from this node down to a leaf,
+    /// counting both ends. Every node keeps its height, so this takes O(1).
+    pub fn height(&self) -> i32 {
+        self.tree.height(&self.node)
+    }
+
+    pub fn left(&self) -> Option<Self> {
+        self.tree
+            .left(&self.node)
+            .map(|node| NodeRef::new(self.tree, node))
+    }
+
+    pub fn right(&self) -> Option<Self> {
+        self.tree
+            .right(&self.node)
+            .map(|node| NodeRef::new(self.tree, node))
+    }
+}
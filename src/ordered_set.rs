@@ -0,0 +1,44 @@
+use std::fmt::Display;
+use std::ops::Bound;
+
+/// Operations shared by the balanced trees of this crate, so that code can be written once
+/// and be generic or `dyn` over the kind of tree.
+pub trait OrderedSet<K: Ord + Clone> {
+    /// Inserts `key` unless it is already present, and returns whether it was inserted.
+    fn insert(&mut self, key: K) -> bool;
+
+    /// Removes `key` and returns it, or `None` if it was not present.
+    fn remove(&mut self, key: &K) -> Option<K>;
+
+    fn contains(&self, key: &K) -> bool;
+
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn height(&self) -> i32;
+
+    /// Iterates over the keys in ascending order.
+    fn iter(&self) -> Box<dyn Iterator<Item = &K> + '_>;
+
+    /// Iterates over the keys between `start` and `end` in ascending order.
+    fn range(&self, start: Bound<&K>, end: Bound<&K>) -> Box<dyn Iterator<Item = &K> + '_>;
+
+    fn min(&self) -> Option<K>;
+
+    fn max(&self) -> Option<K>;
+
+    fn inorder(&self) -> Vec<K>;
+
+    fn preorder(&self) -> Vec<K>;
+
+    fn postorder(&self) -> Vec<K>;
+
+    fn count_leaves(&self) -> i32;
+
+    fn print_structure(&self)
+    where
+        K: Display;
+}
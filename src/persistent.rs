@@ -0,0 +1,596 @@
+use crate::common_tree::NodeColor;
+use std::cmp::{max, Ordering};
+use std::iter::FusedIterator;
+use std::rc::Rc;
+
+/// A node shared between every version of a persistent tree that contains it. Nodes are never
+/// changed once built, so they have no parent links: a change copies the path from the root
+/// down to it and reuses every subtree off that path.
+struct PersistentNode<K> {
+    key: K,
+    left: Branch<K>,
+    right: Branch<K>,
+    height: i32,
+    size: usize,
+    // Only set in red-black trees
+    color: Option<NodeColor>,
+}
+
+type Branch<K> = Option<Rc<PersistentNode<K>>>;
+
+fn height<K>(branch: &Branch<K>) -> i32 {
+    branch.as_ref().map_or(0, |node| node.height)
+}
+
+fn size<K>(branch: &Branch<K>) -> usize {
+    branch.as_ref().map_or(0, |node| node.size)
+}
+
+fn node<K>(left: Branch<K>, key: K, right: Branch<K>, color: Option<NodeColor>) -> Branch<K> {
+    Some(Rc::new(PersistentNode {
+        height: 1 + max(height(&left), height(&right)),
+        size: 1 + size(&left) + size(&right),
+        key,
+        left,
+        right,
+        color,
+    }))
+}
+
+fn contains<K: Ord>(mut branch: &Branch<K>, key: &K) -> bool {
+    while let Some(node) = branch {
+        branch = match key.cmp(&node.key) {
+            Ordering::Less => &node.left,
+            Ordering::Greater => &node.right,
+            Ordering::Equal => return true,
+        };
+    }
+    false
+}
+
+fn first<K>(branch: &Branch<K>) -> Option<&K> {
+    let mut node = branch.as_ref()?;
+    while let Some(left) = &node.left {
+        node = left;
+    }
+    Some(&node.key)
+}
+
+fn last<K>(branch: &Branch<K>) -> Option<&K> {
+    let mut node = branch.as_ref()?;
+    while let Some(right) = &node.right {
+        node = right;
+    }
+    Some(&node.key)
+}
+
+/// An AVL tree whose versions are all kept. `insert` and `remove` leave the tree they are
+/// called on unchanged and return a new version, which shares every subtree off the changed
+/// path with the old one. Cloning a version takes O(1).
+pub struct PersistentAVLTree<K> {
+    root: Branch<K>,
+}
+
+/// A red-black tree whose versions are all kept, in the same way as [`PersistentAVLTree`].
+pub struct PersistentRedBlackTree<K> {
+    root: Branch<K>,
+}
+
+impl<K> Clone for PersistentAVLTree<K> {
+    fn clone(&self) -> Self {
+        PersistentAVLTree {
+            root: self.root.clone(),
+        }
+    }
+}
+
+impl<K> Clone for PersistentRedBlackTree<K> {
+    fn clone(&self) -> Self {
+        PersistentRedBlackTree {
+            root: self.root.clone(),
+        }
+    }
+}
+
+impl<K> Default for PersistentAVLTree<K> {
+    fn default() -> Self {
+        PersistentAVLTree { root: None }
+    }
+}
+
+impl<K> Default for PersistentRedBlackTree<K> {
+    fn default() -> Self {
+        PersistentRedBlackTree { root: None }
+    }
+}
+
+impl<K: Ord + Clone> PersistentAVLTree<K> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    pub fn len(&self) -> usize {
+        size(&self.root)
+    }
+
+    pub fn height(&self) -> i32 {
+        height(&self.root)
+    }
+
+    pub fn contains(&self, key: &K) -> bool {
+        contains(&self.root, key)
+    }
+
+    pub fn first(&self) -> Option<&K> {
+        first(&self.root)
+    }
+
+    pub fn last(&self) -> Option<&K> {
+        last(&self.root)
+    }
+
+    /// Iterates over the keys of this version in ascending order.
+    pub fn iter(&self) -> Iter<'_, K> {
+        Iter::new(&self.root)
+    }
+
+    /// Returns a version that also holds `key`, copying the O(log n) nodes on its path.
+    pub fn insert(&self, key: K) -> Self {
+        match Self::insert_into(&self.root, key) {
+            Some(root) => PersistentAVLTree { root },
+            None => self.clone(),
+        }
+    }
+
+    /// Returns a version without `key`, copying the O(log n) nodes on its path.
+    pub fn remove(&self, key: &K) -> Self {
+        match Self::remove_from(&self.root, key) {
+            Some(root) => PersistentAVLTree { root },
+            None => self.clone(),
+        }
+    }
+
+    // Returns `None` if the key is already present, so nothing has to be copied
+    fn insert_into(branch: &Branch<K>, key: K) -> Option<Branch<K>> {
+        let Some(current) = branch else {
+            return Some(node(None, key, None, None));
+        };
+        match key.cmp(&current.key) {
+            Ordering::Less => {
+                let left = Self::insert_into(&current.left, key)?;
+                Some(Self::balance(
+                    left,
+                    current.key.clone(),
+                    current.right.clone(),
+                ))
+            }
+            Ordering::Greater => {
+                let right = Self::insert_into(&current.right, key)?;
+                Some(Self::balance(
+                    current.left.clone(),
+                    current.key.clone(),
+                    right,
+                ))
+            }
+            Ordering::Equal => None,
+        }
+    }
+
+    // Returns `None` if the key is missing, so nothing has to be copied
+    fn remove_from(branch: &Branch<K>, key: &K) -> Option<Branch<K>> {
+        let current = branch.as_ref()?;
+        match key.cmp(&current.key) {
+            Ordering::Less => {
+                let left = Self::remove_from(&current.left, key)?;
+                Some(Self::balance(
+                    left,
+                    current.key.clone(),
+                    current.right.clone(),
+                ))
+            }
+            Ordering::Greater => {
+                let right = Self::remove_from(&current.right, key)?;
+                Some(Self::balance(
+                    current.left.clone(),
+                    current.key.clone(),
+                    right,
+                ))
+            }
+            Ordering::Equal => match (&current.left, &current.right) {
+                (None, right) => Some(right.clone()),
+                (left, None) => Some(left.clone()),
+                // The inorder successor takes the place of the removed key
+                (left, Some(right)) => {
+                    let (right, successor) = Self::remove_min(right);
+                    Some(Self::balance(left.clone(), successor, right))
+                }
+            },
+        }
+    }
+
+    // Returns the subtree without its smallest key, and that key
+    fn remove_min(current: &Rc<PersistentNode<K>>) -> (Branch<K>, K) {
+        match &current.left {
+            Some(left) => {
+                let (left, min) = Self::remove_min(left);
+                let balanced = Self::balance(left, current.key.clone(), current.right.clone());
+                (balanced, min)
+            }
+            None => (current.right.clone(), current.key.clone()),
+        }
+    }
+
+    // Builds a node from subtrees whose heights differ by at most 2, with the same rotations
+    // as `AVLTree::balance`, except that the rotated nodes are built anew instead of relinked
+    fn balance(left: Branch<K>, key: K, right: Branch<K>) -> Branch<K> {
+        let diff = height(&left) - height(&right);
+        if diff > 1 {
+            let left = left.unwrap();
+            if height(&left.left) >= height(&left.right) {
+                // Rotate right
+                let right = node(left.right.clone(), key, right, None);
+                return node(left.left.clone(), left.key.clone(), right, None);
+            }
+            // Rotate the left child left, then this node right
+            let pivot = left.right.as_ref().unwrap();
+            let new_left = node(
+                left.left.clone(),
+                left.key.clone(),
+                pivot.left.clone(),
+                None,
+            );
+            let new_right = node(pivot.right.clone(), key, right, None);
+            return node(new_left, pivot.key.clone(), new_right, None);
+        } else if diff < -1 {
+            let right = right.unwrap();
+            if height(&right.right) >= height(&right.left) {
+                // Rotate left
+                let left = node(left, key, right.left.clone(), None);
+                return node(left, right.key.clone(), right.right.clone(), None);
+            }
+            // Rotate the right child right, then this node left
+            let pivot = right.left.as_ref().unwrap();
+            let new_left = node(left, key, pivot.left.clone(), None);
+            let new_right = node(
+                pivot.right.clone(),
+                right.key.clone(),
+                right.right.clone(),
+                None,
+            );
+            return node(new_left, pivot.key.clone(), new_right, None);
+        }
+        node(left, key, right, None)
+    }
+}
+
+const RED: Option<NodeColor> = Some(NodeColor::Red);
+const BLACK: Option<NodeColor> = Some(NodeColor::Black);
+
+fn is_red<K>(branch: &Branch<K>) -> bool {
+    branch.as_ref().is_some_and(|node| node.color == RED)
+}
+
+fn is_black<K>(branch: &Branch<K>) -> bool {
+    branch.as_ref().is_some_and(|node| node.color == BLACK)
+}
+
+// A copy of a non-empty branch with another color
+fn recolor<K: Clone>(branch: &Branch<K>, color: Option<NodeColor>) -> Branch<K> {
+    let current = branch.as_ref().unwrap();
+    if current.color == color {
+        return branch.clone();
+    }
+    node(
+        current.left.clone(),
+        current.key.clone(),
+        current.right.clone(),
+        color,
+    )
+}
+
+// The fixups follow Kahrs, "Red-black trees with types" (2001). Without parent links, the
+// cases of `RedBlackTree::insert_fixup` and `RedBlackTree::delete_fix` are resolved on the way
+// back up from the changed leaf instead of by walking up from it.
+impl<K: Ord + Clone> PersistentRedBlackTree<K> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    pub fn len(&self) -> usize {
+        size(&self.root)
+    }
+
+    pub fn height(&self) -> i32 {
+        height(&self.root)
+    }
+
+    pub fn contains(&self, key: &K) -> bool {
+        contains(&self.root, key)
+    }
+
+    pub fn first(&self) -> Option<&K> {
+        first(&self.root)
+    }
+
+    pub fn last(&self) -> Option<&K> {
+        last(&self.root)
+    }
+
+    /// Iterates over the keys of this version in ascending order.
+    pub fn iter(&self) -> Iter<'_, K> {
+        Iter::new(&self.root)
+    }
+
+    /// Returns a version that also holds `key`, copying the O(log n) nodes on its path.
+    pub fn insert(&self, key: K) -> Self {
+        match Self::insert_into(&self.root, key) {
+            Some(root) => PersistentRedBlackTree {
+                root: recolor(&root, BLACK),
+            },
+            None => self.clone(),
+        }
+    }
+
+    /// Returns a version without `key`, copying the O(log n) nodes on its path.
+    pub fn remove(&self, key: &K) -> Self {
+        if !self.contains(key) {
+            return self.clone();
+        }
+        let root = Self::remove_from(&self.root, key);
+        PersistentRedBlackTree {
+            root: match root {
+                Some(_) => recolor(&root, BLACK),
+                None => None,
+            },
+        }
+    }
+
+    // Returns `None` if the key is already present, so nothing has to be copied. The result
+    // may have a red root with a red child, which the caller repairs.
+    fn insert_into(branch: &Branch<K>, key: K) -> Option<Branch<K>> {
+        let Some(current) = branch else {
+            return Some(node(None, key, None, RED));
+        };
+        let (left, right) = match key.cmp(&current.key) {
+            Ordering::Less => (
+                Self::insert_into(&current.left, key)?,
+                current.right.clone(),
+            ),
+            Ordering::Greater => (
+                current.left.clone(),
+                Self::insert_into(&current.right, key)?,
+            ),
+            Ordering::Equal => return None,
+        };
+        if current.color == RED {
+            return Some(node(left, current.key.clone(), right, RED));
+        }
+        Some(Self::balance(left, current.key.clone(), right))
+    }
+
+    // Removes a key known to be present. The result has one black node less on every path if
+    // the subtree had a black root, and may have a red root otherwise.
+    fn remove_from(branch: &Branch<K>, key: &K) -> Branch<K> {
+        let current = branch.as_ref()?;
+        match key.cmp(&current.key) {
+            Ordering::Less => {
+                let left = Self::remove_from(&current.left, key);
+                if is_black(&current.left) {
+                    Self::balance_left(left, current.key.clone(), current.right.clone())
+                } else {
+                    node(left, current.key.clone(), current.right.clone(), RED)
+                }
+            }
+            Ordering::Greater => {
+                let right = Self::remove_from(&current.right, key);
+                if is_black(&current.right) {
+                    Self::balance_right(current.left.clone(), current.key.clone(), right)
+                } else {
+                    node(current.left.clone(), current.key.clone(), right, RED)
+                }
+            }
+            Ordering::Equal => Self::fuse(&current.left, &current.right),
+        }
+    }
+
+    // Builds a black node, resolving a red node with a red child directly below it, or two red
+    // children, by rotating and recoloring as the red-uncle and black-uncle cases of
+    // `insert_fixup` do
+    fn balance(left: Branch<K>, key: K, right: Branch<K>) -> Branch<K> {
+        if is_red(&left) && is_red(&right) {
+            return node(recolor(&left, BLACK), key, recolor(&right, BLACK), RED);
+        }
+        if let Some(l) = left.as_ref().filter(|_| is_red(&left)) {
+            if is_red(&l.left) {
+                let new_right = node(l.right.clone(), key, right, BLACK);
+                return node(recolor(&l.left, BLACK), l.key.clone(), new_right, RED);
+            }
+            if let Some(lr) = l.right.as_ref().filter(|_| is_red(&l.right)) {
+                let new_left = node(l.left.clone(), l.key.clone(), lr.left.clone(), BLACK);
+                let new_right = node(lr.right.clone(), key, right, BLACK);
+                return node(new_left, lr.key.clone(), new_right, RED);
+            }
+        }
+        if let Some(r) = right.as_ref().filter(|_| is_red(&right)) {
+            if is_red(&r.right) {
+                let new_left = node(left, key, r.left.clone(), BLACK);
+                return node(new_left, r.key.clone(), recolor(&r.right, BLACK), RED);
+            }
+            if let Some(rl) = r.left.as_ref().filter(|_| is_red(&r.left)) {
+                let new_left = node(left, key, rl.left.clone(), BLACK);
+                let new_right = node(rl.right.clone(), r.key.clone(), r.right.clone(), BLACK);
+                return node(new_left, rl.key.clone(), new_right, RED);
+            }
+        }
+        node(left, key, right, BLACK)
+    }
+
+    // Builds a node whose left subtree lost a black node, like the cases of `delete_fix` where
+    // the doubly black node is a left child
+    fn balance_left(left: Branch<K>, key: K, right: Branch<K>) -> Branch<K> {
+        if is_red(&left) {
+            return node(recolor(&left, BLACK), key, right, RED);
+        }
+        if is_black(&right) {
+            return Self::balance(left, key, recolor(&right, RED));
+        }
+        // A red sibling with a black left child
+        let sibling = right
+            .as_ref()
+            .expect("sibling of a shortened subtree must exist");
+        let nephew = sibling
+            .left
+            .as_ref()
+            .expect("red sibling must have black children");
+        let new_left = node(left, key, nephew.left.clone(), BLACK);
+        let new_right = Self::balance(
+            nephew.right.clone(),
+            sibling.key.clone(),
+            recolor(&sibling.right, RED),
+        );
+        node(new_left, nephew.key.clone(), new_right, RED)
+    }
+
+    // The mirror image of `balance_left`
+    fn balance_right(left: Branch<K>, key: K, right: Branch<K>) -> Branch<K> {
+        if is_red(&right) {
+            return node(left, key, recolor(&right, BLACK), RED);
+        }
+        if is_black(&left) {
+            return Self::balance(recolor(&left, RED), key, right);
+        }
+        let sibling = left
+            .as_ref()
+            .expect("sibling of a shortened subtree must exist");
+        let nephew = sibling
+            .right
+            .as_ref()
+            .expect("red sibling must have black children");
+        let new_left = Self::balance(
+            recolor(&sibling.left, RED),
+            sibling.key.clone(),
+            nephew.left.clone(),
+        );
+        let new_right = node(nephew.right.clone(), key, right, BLACK);
+        node(new_left, nephew.key.clone(), new_right, RED)
+    }
+
+    // Joins the two subtrees of a removed node, whose keys are all ordered and whose black
+    // heights are equal
+    fn fuse(left: &Branch<K>, right: &Branch<K>) -> Branch<K> {
+        let (l, r) = match (left, right) {
+            (None, _) => return right.clone(),
+            (_, None) => return left.clone(),
+            (Some(l), Some(r)) => (l, r),
+        };
+        match (l.color == RED, r.color == RED) {
+            (true, true) => {
+                let middle = Self::fuse(&l.right, &r.left);
+                match middle.as_ref().filter(|_| is_red(&middle)) {
+                    Some(m) => {
+                        let new_left = node(l.left.clone(), l.key.clone(), m.left.clone(), RED);
+                        let new_right = node(m.right.clone(), r.key.clone(), r.right.clone(), RED);
+                        node(new_left, m.key.clone(), new_right, RED)
+                    }
+                    None => {
+                        let new_right = node(middle, r.key.clone(), r.right.clone(), RED);
+                        node(l.left.clone(), l.key.clone(), new_right, RED)
+                    }
+                }
+            }
+            (false, false) => {
+                let middle = Self::fuse(&l.right, &r.left);
+                match middle.as_ref().filter(|_| is_red(&middle)) {
+                    Some(m) => {
+                        let new_left = node(l.left.clone(), l.key.clone(), m.left.clone(), BLACK);
+                        let new_right =
+                            node(m.right.clone(), r.key.clone(), r.right.clone(), BLACK);
+                        node(new_left, m.key.clone(), new_right, RED)
+                    }
+                    None => {
+                        let new_right = node(middle, r.key.clone(), r.right.clone(), BLACK);
+                        Self::balance_left(l.left.clone(), l.key.clone(), new_right)
+                    }
+                }
+            }
+            (false, true) => {
+                let new_left = Self::fuse(left, &r.left);
+                node(new_left, r.key.clone(), r.right.clone(), RED)
+            }
+            (true, false) => {
+                let new_right = Self::fuse(&l.right, right);
+                node(l.left.clone(), l.key.clone(), new_right, RED)
+            }
+        }
+    }
+}
+
+impl<'a, K: Ord + Clone> IntoIterator for &'a PersistentAVLTree<K> {
+    type Item = &'a K;
+    type IntoIter = Iter<'a, K>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, K: Ord + Clone> IntoIterator for &'a PersistentRedBlackTree<K> {
+    type Item = &'a K;
+    type IntoIter = Iter<'a, K>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// In-order iterator over the keys of one version of a persistent tree.
+pub struct Iter<'a, K> {
+    // Nodes whose key has not been yielded yet, with the next one on top
+    stack: Vec<&'a PersistentNode<K>>,
+    len: usize,
+}
+
+impl<'a, K> Iter<'a, K> {
+    fn new(root: &'a Branch<K>) -> Self {
+        let mut iter = Iter {
+            stack: Vec::new(),
+            len: size(root),
+        };
+        iter.push_left(root);
+        iter
+    }
+
+    fn push_left(&mut self, mut branch: &'a Branch<K>) {
+        while let Some(node) = branch {
+            self.stack.push(node);
+            branch = &node.left;
+        }
+    }
+}
+
+impl<'a, K> Iterator for Iter<'a, K> {
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<&'a K> {
+        let node = self.stack.pop()?;
+        self.push_left(&node.right);
+        self.len -= 1;
+        Some(&node.key)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<K> ExactSizeIterator for Iter<'_, K> {}
+
+impl<K> FusedIterator for Iter<'_, K> {}
@@ -1,494 +1,1472 @@
-use crate::common_tree::{CommonTree, GenericTree, Node, NodeColor, Tree};
-use std::cell::RefCell;
-use std::rc::Rc;
+use crate::augment::{self, Augment, Summarized};
+use crate::avl_tree::AVLTree;
+use crate::common_tree::{CommonTree, NodeColor};
+use crate::cursor::{Cursor, CursorMut};
+use crate::entry::{Entry, Path};
+use crate::error::{Invariant, InvariantViolation, TreeError};
+use crate::iter::{IntoIter, Iter, MultiIntoIter, MultiIter, Range};
+use crate::node::NodeRef;
+use crate::ordered_set::OrderedSet;
+use crate::set_ops::{self, Difference, Intersection, Join, Operation, SymmetricDifference, Union};
+use crate::storage::{Backend, Link, RcBackend};
+use std::cmp::Ordering;
+use std::fmt::Display;
+use std::ops::{Bound, RangeBounds};
 
-pub struct RedBlackTree {
-    pub tree: CommonTree,
+// A detached subtree together with its black height, the number of black nodes on every path
+// from its root down to a leaf
+type Subtree<K, V, B> = (Option<Link<K, V, B>>, usize);
+
+// A split subtree: the keys before the split key, the node holding it if there is one, and the
+// keys after it
+type Split<K, V, B> = (Subtree<K, V, B>, Option<Link<K, V, B>>, Subtree<K, V, B>);
+
+pub struct RedBlackTree<K, B: Backend = RcBackend> {
+    pub(crate) tree: CommonTree<K, (), B>,
 }
 
-impl RedBlackTree {
-    pub fn new() -> Self {
+/// An ordered map backed by the same rebalancing code as [`RedBlackTree`].
+pub struct RedBlackMap<K, V, B: Backend = RcBackend> {
+    tree: CommonTree<K, V, B>,
+}
+
+/// An ordered map backed by the same rebalancing code as [`RedBlackTree`], where every node also
+/// keeps the [`Augment`] summary of its subtree, so that any range of keys can be summarized in
+/// O(log n). Values are only changed through `insert`, which keeps the summaries up to date.
+pub struct RedBlackAugmentedMap<K, V, A: Augment<K, V>, B: Backend = RcBackend> {
+    tree: CommonTree<K, Summarized<K, V, A>, B>,
+}
+
+/// An ordered multiset backed by the same rebalancing code as [`RedBlackTree`]. Each distinct
+/// key has one node, which counts how many copies of the key were inserted.
+pub struct RedBlackMultiset<K, B: Backend = RcBackend> {
+    tree: CommonTree<K, usize, B>,
+    // Copies of all keys together, where the tree itself counts distinct keys
+    len: usize,
+}
+
+impl<K: Ord, B: Backend> Default for RedBlackTree<K, B> {
+    fn default() -> Self {
         RedBlackTree {
-            tree: CommonTree::new(),
+            tree: CommonTree::default(),
         }
     }
+}
 
-    pub fn insert(&mut self, key: i32) {
-        let new_node = Rc::new(RefCell::new(Node {
-            key,
-            left: None,
-            right: None,
-            parent: None,
-            height: 1,
-            color: Some(NodeColor::Red), // New nodes are always red in Red-Black Tree
-        }));
+impl<K: Ord> RedBlackTree<K> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
 
-        let mut p_inserted = None; // This will be the parent of the inserted node
-        let mut root = self.tree.root.clone(); // Start from the root of the tree
+impl<K: Ord, B: Backend> RedBlackTree<K, B> {
+    /// Builds a perfectly balanced tree from keys in strictly ascending order, in O(n).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TreeError::NotAscending`] if a key is not larger than the one before it.
+    pub fn from_sorted_iter<I: IntoIterator<Item = K>>(iter: I) -> Result<Self, TreeError> {
+        let mut tree = CommonTree::default();
+        tree.build_sorted(iter.into_iter().map(|key| (key, ())), true)?;
+        Ok(RedBlackTree { tree })
+    }
 
-        while let Some(current) = root {
-            p_inserted = Some(Rc::clone(&current)); // Keep track of the potential parent
-                                                    // Scope to limit the duration of borrow
-            let next = {
-                if key < current.borrow().key {
-                    current.borrow().left.clone()
-                } else {
-                    current.borrow().right.clone()
-                }
-            };
-            root = next;
-        }
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
 
-        // Set the parent of the new node
-        new_node.borrow_mut().parent = p_inserted.clone();
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    pub fn contains(&self, key: &K) -> bool {
+        self.tree.search(key).is_some()
+    }
 
-        // Insert the new node into the tree
-        match p_inserted {
-            None => {
-                // Tree was empty, this node becomes root
-                new_node.borrow_mut().color = Some(NodeColor::Black); // Make root black
-                self.tree.root = Some(new_node);
+    /// Returns the number of nodes on the longest path from the root down to a leaf.
+    pub fn height(&self) -> i32 {
+        self.tree.get_height()
+    }
+
+    /// Iterates over the keys in ascending order.
+    pub fn iter(&self) -> Iter<'_, K, (), B> {
+        self.tree.iter()
+    }
+
+    /// Iterates in ascending order over the keys that fall within `range`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start of the range lies after its end.
+    pub fn range<R: RangeBounds<K>>(&self, range: R) -> Range<'_, K, (), B> {
+        self.tree.range(range)
+    }
+
+    /// Returns the key at position `index` in ascending order.
+    pub fn select(&self, index: usize) -> Option<K>
+    where
+        K: Clone,
+    {
+        self.tree.select(index)
+    }
+
+    /// Returns the number of keys that are smaller than `key`.
+    pub fn rank(&self, key: &K) -> usize {
+        self.tree.rank(key)
+    }
+
+    /// Returns the smallest key.
+    pub fn first(&self) -> Option<K>
+    where
+        K: Clone,
+    {
+        self.tree.min()
+    }
+
+    /// Returns the largest key.
+    pub fn last(&self) -> Option<K>
+    where
+        K: Clone,
+    {
+        self.tree.max()
+    }
+
+    /// Returns the largest key that is smaller than or equal to `key`.
+    pub fn floor(&self, key: &K) -> Option<K>
+    where
+        K: Clone,
+    {
+        self.tree.floor(key)
+    }
+
+    /// Returns the smallest key that is larger than or equal to `key`.
+    pub fn ceiling(&self, key: &K) -> Option<K>
+    where
+        K: Clone,
+    {
+        self.tree.ceiling(key)
+    }
+
+    /// Returns the largest key that is strictly smaller than `key`.
+    pub fn predecessor(&self, key: &K) -> Option<K>
+    where
+        K: Clone,
+    {
+        self.tree.predecessor(key)
+    }
+
+    /// Returns the smallest key that is strictly larger than `key`.
+    pub fn successor(&self, key: &K) -> Option<K>
+    where
+        K: Clone,
+    {
+        self.tree.successor(key)
+    }
+
+    /// Inserts `key` unless it is already present, and returns whether it was inserted. See
+    /// [`RedBlackMultiset`] for counting duplicates instead.
+    pub fn insert(&mut self, key: K) -> bool {
+        match self.entry(key) {
+            Entry::Vacant(entry) => {
+                entry.insert(());
+                true
             }
-            Some(parent) => {
-                if key < parent.borrow().key {
-                    parent.borrow_mut().left = Some(new_node.clone());
+            Entry::Occupied(_) => false,
+        }
+    }
+
+    /// Looks up `key` once, for inserting it or reaching it without another search.
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, (), B> {
+        Entry::new(&mut self.tree, key, Self::attach)
+    }
+
+    /// Returns a cursor at the smallest key, or past the ends if the tree is empty.
+    pub fn cursor_front(&self) -> Cursor<'_, K, (), B> {
+        Cursor::new(&self.tree, self.tree.first_node())
+    }
+
+    /// Returns a cursor at the largest key, or past the ends if the tree is empty.
+    pub fn cursor_back(&self) -> Cursor<'_, K, (), B> {
+        Cursor::new(&self.tree, self.tree.last_node())
+    }
+
+    /// Returns a cursor at the smallest key within `bound`, or past the ends if there is none.
+    pub fn lower_bound(&self, bound: Bound<&K>) -> Cursor<'_, K, (), B> {
+        Cursor::new(&self.tree, self.tree.first_node_after(bound))
+    }
+
+    pub fn cursor_front_mut(&mut self) -> CursorMut<'_, K, (), B> {
+        let first = self.tree.first_node();
+        CursorMut::new(&mut self.tree, first, Self::attach, Self::delete_node)
+    }
+
+    pub fn cursor_back_mut(&mut self) -> CursorMut<'_, K, (), B> {
+        let last = self.tree.last_node();
+        CursorMut::new(&mut self.tree, last, Self::attach, Self::delete_node)
+    }
+
+    pub fn lower_bound_mut(&mut self, bound: Bound<&K>) -> CursorMut<'_, K, (), B> {
+        let node = self.tree.first_node_after(bound);
+        CursorMut::new(&mut self.tree, node, Self::attach, Self::delete_node)
+    }
+
+    /// Returns a read-only view of the root, from which the shape of the tree can be walked,
+    /// or `None` if the tree is empty.
+    pub fn root(&self) -> Option<NodeRef<'_, K, (), B>> {
+        NodeRef::root(&self.tree)
+    }
+
+    // Links `node` below the end of `path` and restores the red-black properties from there
+    pub(crate) fn attach<V>(
+        tree: &mut CommonTree<K, V, B>,
+        mut path: Path<K, V, B>,
+        node: Link<K, V, B>,
+    ) {
+        tree.set_color(&node, Some(NodeColor::Red));
+        match path.pop() {
+            None => tree.root = Some(node),
+            Some((parent, ordering)) => {
+                tree.set_parent(&node, Some(parent.clone()));
+                if ordering == Ordering::Less {
+                    tree.set_left(&parent, Some(node.clone()));
                 } else {
-                    parent.borrow_mut().right = Some(new_node.clone());
+                    tree.set_right(&parent, Some(node.clone()));
                 }
-                self.tree.root = self.insert_fixup(new_node.clone());
+                Self::update_sizes_from(tree, Some(parent));
+                Self::insert_fixup(tree, node);
             }
         }
-        self.ensure_black_root();
+        Self::ensure_black_root(tree);
+        tree.len += 1;
     }
 
-    fn insert_fixup(&mut self, curr: Tree) -> GenericTree {
+    fn insert_fixup<V>(tree: &mut CommonTree<K, V, B>, curr: Link<K, V, B>) {
         let mut node = curr.clone();
-        while curr.borrow().parent.clone().is_some() && node.borrow().parent.clone().is_some() {
-            let mut parent = node.borrow().parent.as_ref().unwrap().clone();
-            if parent.borrow().color == Some(NodeColor::Black) {
+        while tree.parent(&curr).is_some() && tree.parent(&node).is_some() {
+            let mut parent = tree.parent(&node).unwrap();
+            if tree.color(&parent) == Some(NodeColor::Black) {
                 break;
             }
 
-            let grandparent = parent.borrow().parent.clone();
-            let is_parent_left = if let Some(grandparent_ref) = grandparent.as_ref() {
-                if let Some(left_child_ref) = grandparent_ref.borrow().left.as_ref() {
-                    left_child_ref.borrow().key == parent.borrow().key
-                } else {
-                    false // If the left child does not exist, the parent cannot be the left child
-                }
-            } else {
-                false // If the grandparent does not exist, the parent cannot be the left child
-            };
+            let grandparent = tree.parent(&parent);
+            // If the grandparent does not exist, the parent cannot be the left child
+            let is_parent_left = grandparent
+                .as_ref()
+                .is_some_and(|grandparent| tree.left(grandparent).as_ref() == Some(&parent));
 
-            let uncle_node = if let Some(grandparent_ref) = grandparent.as_ref() {
+            let uncle_node = grandparent.as_ref().and_then(|grandparent| {
                 if is_parent_left {
-                    grandparent_ref.borrow().right.clone()
+                    tree.right(grandparent)
                 } else {
-                    grandparent_ref.borrow().left.clone()
+                    tree.left(grandparent)
                 }
-            } else {
-                None
-            };
+            });
 
             match uncle_node {
-                Some(uncle) if uncle.borrow().color == Some(NodeColor::Red) => {
-                    uncle.borrow_mut().color = Some(NodeColor::Black);
-                    parent.borrow_mut().color = Some(NodeColor::Black);
-                    parent.borrow().parent.as_ref().unwrap().borrow_mut().color =
-                        Some(NodeColor::Red);
-                    node = parent.borrow().clone().parent.clone().unwrap();
+                Some(uncle) if tree.color(&uncle) == Some(NodeColor::Red) => {
+                    tree.set_color(&uncle, Some(NodeColor::Black));
+                    tree.set_color(&parent, Some(NodeColor::Black));
+                    let grandparent = tree.parent(&parent).unwrap();
+                    tree.set_color(&grandparent, Some(NodeColor::Red));
+                    node = grandparent;
                 }
                 _ => {
                     if is_parent_left {
-                        if parent.borrow().clone().key < node.borrow().clone().key {
-                            let parent_node_clone = node.borrow().parent.as_ref().unwrap().clone();
-                            node = parent_node_clone;
-                            Self::rotate_left(node.clone());
-                            let new_parent_node_clone =
-                                node.borrow().parent.as_ref().unwrap().clone();
-                            parent = new_parent_node_clone;
+                        if tree.right(&parent).as_ref() == Some(&node) {
+                            node = tree.parent(&node).unwrap();
+                            Self::rotate_left(tree, node.clone());
+                            parent = tree.parent(&node).unwrap();
                         }
-                        parent.borrow().parent.as_ref().unwrap().borrow_mut().color =
-                            Some(NodeColor::Red);
-                        parent.borrow_mut().color = Some(NodeColor::Black);
-                        let grandparent = node
-                            .borrow()
-                            .parent
-                            .as_ref()
-                            .unwrap()
-                            .borrow()
-                            .parent
-                            .as_ref()
-                            .unwrap()
-                            .clone();
-                        Self::rotate_right(grandparent);
+                        let grandparent = tree.parent(&parent).unwrap();
+                        tree.set_color(&grandparent, Some(NodeColor::Red));
+                        tree.set_color(&parent, Some(NodeColor::Black));
+                        Self::rotate_right(tree, grandparent);
                     } else {
-                        if parent.borrow().clone().key > node.borrow().clone().key {
-                            let parent_node_clone = node.borrow().parent.as_ref().unwrap().clone();
-                            node = parent_node_clone;
-                            Self::rotate_right(node.clone());
-                            let new_parent_node_clone =
-                                node.borrow().parent.as_ref().unwrap().clone();
-                            parent = new_parent_node_clone;
+                        if tree.left(&parent).as_ref() == Some(&node) {
+                            node = tree.parent(&node).unwrap();
+                            Self::rotate_right(tree, node.clone());
+                            parent = tree.parent(&node).unwrap();
                         }
-                        parent.borrow().parent.as_ref().unwrap().borrow_mut().color =
-                            Some(NodeColor::Red);
-                        parent.borrow_mut().color = Some(NodeColor::Black);
-                        let grandparent = node
-                            .borrow()
-                            .parent
-                            .as_ref()
-                            .unwrap()
-                            .borrow()
-                            .parent
-                            .as_ref()
-                            .unwrap()
-                            .clone();
-                        Self::rotate_left(grandparent);
+                        let grandparent = tree.parent(&parent).unwrap();
+                        tree.set_color(&grandparent, Some(NodeColor::Red));
+                        tree.set_color(&parent, Some(NodeColor::Black));
+                        Self::rotate_left(tree, grandparent);
                     }
                     break;
                 }
             }
         }
-        self.find_root(node)
-    }
-
-    fn find_root(&self, node: Tree) -> GenericTree {
-        match node.borrow().parent {
-            Some(ref parent) => self.find_root(parent.clone()),
-            None => Some(node.clone()),
-        }
     }
 
-    fn ensure_black_root(&mut self) {
-        if let Some(ref root) = self.tree.root {
-            root.borrow_mut().color = Some(NodeColor::Black);
+    fn ensure_black_root<V>(tree: &mut CommonTree<K, V, B>) {
+        if let Some(root) = tree.root.clone() {
+            tree.set_color(&root, Some(NodeColor::Black));
         }
     }
 
-    fn rotate_left(node: Tree) {
-        let parent = node.clone();
-        let right_child = parent
-            .borrow()
-            .right
-            .clone()
-            .expect("Right node must exist");
+    // Rotations keep `tree.root` up to date when they rotate at the root
+    fn rotate_left<V>(tree: &mut CommonTree<K, V, B>, node: Link<K, V, B>) {
+        let parent = node;
+        let right_child = tree.right(&parent).expect("Right node must exist");
 
-        let right_child_left = right_child.borrow().left.clone();
-        parent.borrow_mut().right = right_child_left.clone();
+        let right_child_left = tree.left(&right_child);
+        tree.set_right(&parent, right_child_left.clone());
 
         if let Some(ref right_child_left) = right_child_left {
-            right_child_left.borrow_mut().parent = Some(parent.clone());
+            tree.set_parent(right_child_left, Some(parent.clone()));
         }
 
-        right_child.borrow_mut().left = Some(parent.clone());
-        right_child.borrow_mut().parent = parent.borrow().parent.clone();
+        let grandparent = tree.parent(&parent);
+        tree.set_left(&right_child, Some(parent.clone()));
+        tree.set_parent(&right_child, grandparent.clone());
 
-        if let Some(ref grandparent) = parent.borrow().parent {
-            let mut grandparent_borrow = grandparent.borrow_mut();
-            if grandparent_borrow
-                .right
-                .as_ref()
-                .map_or(false, |r| Rc::ptr_eq(r, &parent))
-            {
-                grandparent_borrow.right = Some(right_child.clone());
-            } else {
-                grandparent_borrow.left = Some(right_child.clone());
+        match grandparent {
+            Some(grandparent) => {
+                if tree.right(&grandparent).as_ref() == Some(&parent) {
+                    tree.set_right(&grandparent, Some(right_child.clone()));
+                } else {
+                    tree.set_left(&grandparent, Some(right_child.clone()));
+                }
             }
+            None => tree.root = Some(right_child.clone()),
         }
 
-        parent.borrow_mut().parent = Some(right_child.clone());
+        tree.set_parent(&parent, Some(right_child.clone()));
+        tree.update_size(&parent);
+        // The nodes above keep their sizes but may change height
+        Self::update_sizes_from(tree, Some(right_child));
     }
 
-    fn rotate_right(node: Tree) {
-        let parent = node.clone();
-        let left_child = parent.borrow().left.clone().expect("Left node must exist");
+    fn rotate_right<V>(tree: &mut CommonTree<K, V, B>, node: Link<K, V, B>) {
+        let parent = node;
+        let left_child = tree.left(&parent).expect("Left node must exist");
 
-        let left_child_right = left_child.borrow().right.clone();
-        parent.borrow_mut().left = left_child_right.clone();
+        let left_child_right = tree.right(&left_child);
+        tree.set_left(&parent, left_child_right.clone());
 
         if let Some(ref left_child_right) = left_child_right {
-            left_child_right.borrow_mut().parent = Some(parent.clone());
+            tree.set_parent(left_child_right, Some(parent.clone()));
         }
 
-        left_child.borrow_mut().right = Some(parent.clone());
-        left_child.borrow_mut().parent = parent.borrow().parent.clone();
+        let grandparent = tree.parent(&parent);
+        tree.set_right(&left_child, Some(parent.clone()));
+        tree.set_parent(&left_child, grandparent.clone());
 
-        if let Some(ref grandparent) = parent.borrow().parent {
-            let mut grandparent_borrow = grandparent.borrow_mut();
-            if grandparent_borrow
-                .left
-                .as_ref()
-                .map_or(false, |l| Rc::ptr_eq(l, &parent))
-            {
-                grandparent_borrow.left = Some(left_child.clone());
-            } else {
-                grandparent_borrow.right = Some(left_child.clone());
+        match grandparent {
+            Some(grandparent) => {
+                if tree.left(&grandparent).as_ref() == Some(&parent) {
+                    tree.set_left(&grandparent, Some(left_child.clone()));
+                } else {
+                    tree.set_right(&grandparent, Some(left_child.clone()));
+                }
             }
+            None => tree.root = Some(left_child.clone()),
         }
 
-        parent.borrow_mut().parent = Some(left_child.clone());
+        tree.set_parent(&parent, Some(left_child.clone()));
+        tree.update_size(&parent);
+        // The nodes above keep their sizes but may change height
+        Self::update_sizes_from(tree, Some(left_child));
     }
 
-    pub fn delete(&mut self, key: i32) {
-        let node_to_be_deleted: Option<Rc<RefCell<Node>>> = self.tree.search(key);
-        if let Some(node_to_be_deleted) = node_to_be_deleted {
-            // Track the parent and children of the node
-            let mut parent: Option<Rc<RefCell<Node>>> = node_to_be_deleted.borrow().parent.clone();
-            let left_child: Option<Rc<RefCell<Node>>> = node_to_be_deleted.borrow().left.clone();
-            let right_child: Option<Rc<RefCell<Node>>> = node_to_be_deleted.borrow().right.clone();
+    /// Checks every property of a red-black tree: the order of the keys, the subtree sizes,
+    /// the parent links, the colors, the black heights and the cached heights. All broken
+    /// properties are reported.
+    pub fn validate(&self) -> Result<(), Vec<InvariantViolation<K>>>
+    where
+        K: Clone,
+    {
+        let tree = &self.tree;
+        let mut violations = Vec::new();
+        if let Some(root) = &tree.root {
+            if tree.color(root) == Some(NodeColor::Red) {
+                violations.push(tree.violation(root, Invariant::BlackRoot));
+            }
+        }
+        tree.check_root(&mut violations);
+        Self::validate_subtree(tree, &tree.root, None, None, &mut violations);
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(violations)
+        }
+    }
 
-            // Save the color of nodeToBeDeleted
-            let mut u_og_color: Option<NodeColor> = node_to_be_deleted.borrow().color.clone();
+    // Returns the black height of the subtree, counting the missing leaves below it, and its
+    // actual height
+    fn validate_subtree(
+        tree: &CommonTree<K, (), B>,
+        node: &Option<Link<K, (), B>>,
+        lower: Option<&K>,
+        upper: Option<&K>,
+        violations: &mut Vec<InvariantViolation<K>>,
+    ) -> (usize, i32)
+    where
+        K: Clone,
+    {
+        let Some(node) = node else {
+            return (1, 0);
+        };
+        tree.check_node(node, lower, upper, violations);
+        let color = tree.color(node);
+        if color.is_none() {
+            violations.push(tree.violation(node, Invariant::Color));
+        }
+        for child in [tree.left(node), tree.right(node)].into_iter().flatten() {
+            if color == Some(NodeColor::Red) && tree.color(&child) == Some(NodeColor::Red) {
+                violations.push(tree.violation(&child, Invariant::RedRed));
+            }
+        }
+        let key = tree.key(node);
+        let (left_black, left_height) =
+            Self::validate_subtree(tree, &tree.left(node), lower, Some(key), violations);
+        let (right_black, right_height) =
+            Self::validate_subtree(tree, &tree.right(node), Some(key), upper, violations);
+        if left_black != right_black {
+            violations.push(tree.violation(node, Invariant::BlackHeight));
+        }
+        let height = 1 + left_height.max(right_height);
+        if tree.height(node) != height {
+            violations.push(tree.violation(node, Invariant::Height));
+        }
+        let black_height = left_black.max(right_black) + usize::from(color != Some(NodeColor::Red));
+        (black_height, height)
+    }
 
-            // If left child is None assign it to x
-            let x: Option<Rc<RefCell<Node>>>;
+    /// Removes `key` and returns it, or `None` if it was not present.
+    pub fn delete(&mut self, key: &K) -> Option<K> {
+        let node_to_be_deleted = self.tree.search(key)?;
+        Some(Self::delete_node(&mut self.tree, node_to_be_deleted).0)
+    }
 
-            if left_child.is_none() {
-                x = right_child.clone();
-                self.transplant(Some(node_to_be_deleted.clone()), right_child.clone());
-            } else if right_child.is_none() {
-                x = left_child.clone();
-                self.transplant(Some(node_to_be_deleted.clone()), left_child.clone());
-            } else {
-                let y = self.find_min(right_child.clone());
-                u_og_color = y.as_ref().unwrap().borrow().color.clone();
-                x = y.as_ref().unwrap().borrow().right.clone();
-
-                if y.as_ref()
-                    .unwrap()
-                    .borrow()
-                    .parent
-                    .as_ref()
-                    .unwrap()
-                    .borrow()
-                    .key
-                    == node_to_be_deleted.borrow().key
-                {
-                    if let Some(x) = &x {
-                        x.borrow_mut().parent = y.clone();
-                    } else {
-                        parent = y.clone();
-                    }
-                } else {
-                    self.transplant(y.clone(), y.as_ref().unwrap().borrow().right.clone());
-                    y.as_ref().unwrap().borrow_mut().right =
-                        node_to_be_deleted.borrow().right.clone();
-                    y.as_ref()
-                        .unwrap()
-                        .borrow()
-                        .right
-                        .as_ref()
-                        .unwrap()
-                        .borrow_mut()
-                        .parent = y.clone();
-                }
-                self.transplant(Some(node_to_be_deleted.clone()), y.clone());
-                y.as_ref().unwrap().borrow_mut().left = left_child.clone();
-                left_child.as_ref().unwrap().borrow_mut().parent = y.clone();
-                y.as_ref().unwrap().borrow_mut().color = node_to_be_deleted.borrow().color.clone();
-            }
-            if u_og_color == Some(NodeColor::Black) {
-                self.delete_fix(x.clone(), parent.clone());
+    /// Removes and returns the key at position `index` in ascending order.
+    pub fn remove_at(&mut self, index: usize) -> Option<K> {
+        let node = self.tree.select_node(index)?;
+        Some(Self::delete_node(&mut self.tree, node).0)
+    }
+
+    /// Moves every key that is larger than or equal to `key` into a new tree. Takes
+    /// logarithmic time with `RcBackend`. With `ArenaBackend` the smaller half is also copied
+    /// into an arena of its own, which takes time proportional to its size.
+    pub fn split_off(&mut self, key: &K) -> Self {
+        let root = self.tree.root.take();
+        let total = self.tree.len;
+        let root = Self::subtree(&self.tree, root);
+        let (smaller, found, rest) =
+            Self::split_links(&mut self.tree, root, &|tree, node| key.cmp(tree.key(node)));
+        let rest = match found {
+            Some(node) => {
+                let (root, black_height) = Self::join_links(&mut self.tree, (None, 0), node, rest);
+                (Some(root), black_height)
             }
+            None => rest,
+        };
+        let smaller = Self::into_root(&mut self.tree, smaller);
+        let rest = Self::into_root(&mut self.tree, rest);
+        let mut other = Self::default();
+        // Only the smaller half is moved to new storage. When that half is the one staying
+        // here, the two stores trade places afterwards.
+        if self.tree.subtree_size(&rest) <= self.tree.subtree_size(&smaller) {
+            self.tree.root = smaller;
+            other.tree.root = other.tree.adopt(&mut self.tree, rest);
         } else {
-            println!("Key {} not found", key);
+            other.tree.root = rest;
+            self.tree.root = other.tree.adopt(&mut self.tree, smaller);
+            std::mem::swap(&mut self.tree.store, &mut other.tree.store);
         }
+        self.tree.len = self.tree.subtree_size(&self.tree.root);
+        other.tree.len = total - self.tree.len;
+        other
     }
 
-    fn delete_fix(&mut self, x: Option<Tree>, parent: Option<Tree>) {
-        // Track the current parent to make it easier to access its data
-        let mut cur_p: GenericTree = parent.clone();
+    /// Moves every key of `other` into this tree, leaving `other` empty. When all keys of one
+    /// tree are smaller than all keys of the other the two trees are joined, which costs what
+    /// [`Self::join`] costs. Otherwise the keys of `other` are inserted one at a time.
+    pub fn append(&mut self, other: &mut Self) {
+        let mut other = std::mem::take(other);
+        if self.tree.is_empty() {
+            *self = other;
+            return;
+        }
+        if other.tree.is_empty() {
+            return;
+        }
+        // The end nodes are only looked at here, as nodes cannot be freed while linked elsewhere
+        let (precedes, follows) = {
+            let (first, last) = (self.tree.first_node(), self.tree.last_node());
+            let (other_first, other_last) = (other.tree.first_node(), other.tree.last_node());
+            (
+                self.tree.key(&last.unwrap()) < other.tree.key(&other_first.unwrap()),
+                other.tree.key(&other_last.unwrap()) < self.tree.key(&first.unwrap()),
+            )
+        };
+        if precedes {
+            let pivot = other.pop_first().unwrap();
+            *self = Self::join(std::mem::take(self), pivot, other);
+        } else if follows {
+            let pivot = other.pop_last().unwrap();
+            *self = Self::join(other, pivot, std::mem::take(self));
+        } else {
+            for key in other {
+                self.insert(key);
+            }
+        }
+    }
 
-        // Track the current x as it changes after rotations
-        let mut cur_x: GenericTree = x.clone();
+    /// Builds a tree from `left`, `pivot` and `right`, where every key of `left` is smaller
+    /// than `pivot` and every key of `right` is larger. With `RcBackend` this takes time
+    /// proportional to the difference of the two trees' black heights. With `ArenaBackend` the
+    /// smaller tree is also copied into the arena of the larger one, which takes time
+    /// proportional to its size.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the keys are not ordered that way.
+    pub fn join(mut left: Self, pivot: K, mut right: Self) -> Self {
+        assert!(
+            left.tree
+                .last_node()
+                .is_none_or(|last| *left.tree.key(&last) < pivot)
+                && right
+                    .tree
+                    .first_node()
+                    .is_none_or(|first| pivot < *right.tree.key(&first)),
+            "Keys of the joined trees must be ordered around the pivot"
+        );
+        let len = left.tree.len + 1 + right.tree.len;
+        // The smaller tree is moved into the storage of the larger one
+        let (mut joined, left_root, right_root) = if left.tree.len >= right.tree.len {
+            let right_root = right.tree.root.take();
+            let right_root = left.tree.adopt(&mut right.tree, right_root);
+            let left_root = left.tree.root.take();
+            (left, left_root, right_root)
+        } else {
+            let left_root = left.tree.root.take();
+            let left_root = right.tree.adopt(&mut left.tree, left_root);
+            let right_root = right.tree.root.take();
+            (right, left_root, right_root)
+        };
+        let left_height = Self::black_height(&joined.tree, &left_root);
+        let right_height = Self::black_height(&joined.tree, &right_root);
+        let pivot = joined.tree.new_node(pivot, ());
+        let (root, _) = Self::join_links(
+            &mut joined.tree,
+            (left_root, left_height),
+            pivot,
+            (right_root, right_height),
+        );
+        joined.tree.root = Some(root);
+        joined.tree.len = len;
+        joined
+    }
 
-        // Track whether x has become the root
-        let mut x_is_root = cur_p.is_none();
+    fn black_height<V>(tree: &CommonTree<K, V, B>, node: &Option<Link<K, V, B>>) -> usize {
+        let mut height = 0;
+        let mut current = node.clone();
+        while let Some(node) = current {
+            if tree.color(&node) == Some(NodeColor::Black) {
+                height += 1;
+            }
+            current = tree.left(&node);
+        }
+        height
+    }
 
-        // Track when x is black
-        let mut x_is_black = self.check_color(cur_x.clone());
+    // Links two detached subtrees under a pivot that sorts between them and returns the result
+    // with its black height. The pivot goes down the spine of the subtree with the larger black
+    // height until it meets a black node of the other subtree's black height, where it is
+    // linked in red and fixed up like an inserted node. The fixup may rotate at the root, so
+    // `tree.root` holds the joined subtree meanwhile and must not be in use by the caller.
+    fn join_links<V>(
+        tree: &mut CommonTree<K, V, B>,
+        left: Subtree<K, V, B>,
+        pivot: Link<K, V, B>,
+        right: Subtree<K, V, B>,
+    ) -> (Link<K, V, B>, usize) {
+        let ((left, mut left_height), (right, mut right_height)) = (left, right);
+        // Subtrees with a red root are made black, which is always allowed for a root
+        for (node, height) in [(&left, &mut left_height), (&right, &mut right_height)] {
+            if let Some(node) = node {
+                tree.set_parent(node, None);
+                if tree.color(node) == Some(NodeColor::Red) {
+                    tree.set_color(node, Some(NodeColor::Black));
+                    *height += 1;
+                }
+            }
+        }
 
-        // While x is not the root and it is black
-        while !x_is_root && x_is_black {
-            // Variable to hold sibling of x
-            let mut s: Option<Rc<RefCell<Node>>>;
+        if left_height == right_height {
+            tree.set_left(&pivot, left.clone());
+            tree.set_right(&pivot, right.clone());
+            for child in [&left, &right].into_iter().flatten() {
+                tree.set_parent(child, Some(pivot.clone()));
+            }
+            tree.set_parent(&pivot, None);
+            tree.set_color(&pivot, Some(NodeColor::Black));
+            tree.update_size(&pivot);
+            return (pivot, left_height + 1);
+        }
 
-            // Variable to check whether x is left or right child
-            let child_is_left: bool;
+        let descend_right = left_height > right_height;
+        let (root, target_height, other) = if descend_right {
+            (left.unwrap(), right_height, right)
+        } else {
+            (right.unwrap(), left_height, left)
+        };
+        let root_height = left_height.max(right_height);
 
-            // Set it to true when we are left child and false for right
-            if cur_x == cur_p.as_ref().unwrap().borrow().left {
-                child_is_left = true;
-            } else {
-                child_is_left = false;
+        // Walk down the inner spine to the first black node of the target black height
+        let mut parent = None;
+        let mut current = Some(root.clone());
+        let mut height = root_height;
+        while !(height == target_height && Self::check_color(tree, &current)) {
+            let node = current.unwrap();
+            if tree.color(&node) == Some(NodeColor::Black) {
+                height -= 1;
             }
-
-            if child_is_left {
-                // Sibling assigned as right child of parent of x
-                s = cur_p.as_ref().unwrap().borrow().right.clone();
+            current = if descend_right {
+                tree.right(&node)
             } else {
-                // Sibling assigned as left child of parent of x
-                s = cur_p.as_ref().unwrap().borrow().left.clone();
+                tree.left(&node)
+            };
+            parent = Some(node);
+        }
+        let parent = parent.unwrap();
+
+        let (inner_left, inner_right) = if descend_right {
+            (current, other)
+        } else {
+            (other, current)
+        };
+        tree.set_left(&pivot, inner_left.clone());
+        tree.set_right(&pivot, inner_right.clone());
+        for child in [&inner_left, &inner_right].into_iter().flatten() {
+            tree.set_parent(child, Some(pivot.clone()));
+        }
+        tree.set_parent(&pivot, Some(parent.clone()));
+        tree.set_color(&pivot, Some(NodeColor::Red));
+        if descend_right {
+            tree.set_right(&parent, Some(pivot.clone()));
+        } else {
+            tree.set_left(&parent, Some(pivot.clone()));
+        }
+        tree.update_size(&pivot);
+        Self::update_sizes_from(tree, Some(parent));
+
+        tree.root = Some(root);
+        Self::insert_fixup(tree, pivot);
+        let root = tree.root.take().unwrap();
+        // A red root left behind by the fixup turns black, adding one to the black height
+        if tree.color(&root) == Some(NodeColor::Red) {
+            tree.set_color(&root, Some(NodeColor::Black));
+            (root, root_height + 1)
+        } else {
+            (root, root_height)
+        }
+    }
+
+    // Splits a subtree around the key that `target` compares nodes against, by joining the
+    // pieces left and right of the search path back together on the way up. A node holding that
+    // key is handed back unlinked.
+    fn split_links<V>(
+        tree: &mut CommonTree<K, V, B>,
+        (node, black_height): Subtree<K, V, B>,
+        target: &impl Fn(&CommonTree<K, V, B>, &Link<K, V, B>) -> Ordering,
+    ) -> Split<K, V, B> {
+        let Some(node) = node else {
+            return ((None, 0), None, (None, 0));
+        };
+        let (left, right) = (tree.left(&node), tree.right(&node));
+        let child_height = Self::child_black_height(tree, &node, black_height);
+        match target(tree, &node) {
+            Ordering::Greater => {
+                let (smaller, found, larger) =
+                    Self::split_links(tree, (right, child_height), target);
+                let (joined, height) = Self::join_links(tree, (left, child_height), node, smaller);
+                ((Some(joined), height), found, larger)
             }
+            Ordering::Less => {
+                let (smaller, found, larger) =
+                    Self::split_links(tree, (left, child_height), target);
+                let (joined, height) = Self::join_links(tree, larger, node, (right, child_height));
+                (smaller, found, (Some(joined), height))
+            }
+            Ordering::Equal => {
+                Self::unlink_children(tree, &node);
+                ((left, child_height), Some(node), (right, child_height))
+            }
+        }
+    }
 
-            // Need to check so that we do not get an error
-            if s.is_some() {
-                // Check if the sibling is red
-                let s_is_black = self.check_color(s.clone());
-                if !s_is_black {
-                    // If yes then set it to black and change the parent to red
-                    s.as_ref().unwrap().borrow_mut().color = Some(NodeColor::Black);
-                    cur_p.as_ref().unwrap().borrow_mut().color = Some(NodeColor::Red);
-
-                    // Rotate the parent left or right depending on whether
-                    // x is the left or right child. Then reassign sibling
-                    // to new position.
-                    if child_is_left {
-                        Self::rotate_left(cur_p.as_ref().unwrap().clone());
-                        s = cur_p.as_ref().unwrap().borrow().right.clone();
-                    } else {
-                        Self::rotate_right(cur_p.as_ref().unwrap().clone());
-                        s = cur_p.as_ref().unwrap().borrow().left.clone();
-                    }
-                }
+    fn child_black_height<V>(
+        tree: &CommonTree<K, V, B>,
+        node: &Link<K, V, B>,
+        black_height: usize,
+    ) -> usize {
+        if tree.color(node) == Some(NodeColor::Black) {
+            black_height - 1
+        } else {
+            black_height
+        }
+    }
 
-                // Children of sibling of x
-                let mut s_left: GenericTree = s.as_ref().unwrap().borrow().clone().left.clone();
-                let mut s_right: GenericTree = s.as_ref().unwrap().borrow().clone().right.clone();
+    // Detaches both subtrees of `node`, leaving their roots without a parent
+    fn unlink_children<V>(tree: &mut CommonTree<K, V, B>, node: &Link<K, V, B>) {
+        for child in [tree.left(node), tree.right(node)].into_iter().flatten() {
+            tree.set_parent(&child, None);
+        }
+        tree.set_left(node, None);
+        tree.set_right(node, None);
+    }
 
-                // Check the color of both children of sibling
-                let s_left_is_black = self.check_color(s_left.clone());
-                let s_right_is_black = self.check_color(s_right.clone());
+    /// Removes and returns the smallest key.
+    pub fn pop_first(&mut self) -> Option<K> {
+        let node = self.tree.first_node()?;
+        Some(Self::delete_node(&mut self.tree, node).0)
+    }
 
-                // If both are black we are either at a leaf or the root
-                if s_left_is_black && s_right_is_black {
-                    // Set the sibling to Red since both children are black
-                    s.as_ref().unwrap().borrow_mut().color = Some(NodeColor::Red);
+    /// Removes and returns the largest key.
+    pub fn pop_last(&mut self) -> Option<K> {
+        let node = self.tree.last_node()?;
+        Some(Self::delete_node(&mut self.tree, node).0)
+    }
 
-                    // Store x's parent in x
-                    cur_x = cur_p.clone();
+    /// Iterates in ascending order over the keys in this tree, in `other` or in both, where
+    /// `other` is a tree of either kind.
+    pub fn union<'a>(
+        &'a self,
+        other: impl IntoIterator<IntoIter = Iter<'a, K, (), B>>,
+    ) -> Union<'a, K, B> {
+        Union::new(self.iter(), other.into_iter())
+    }
 
-                    // Need to track x's grandparent as its parent after reassignment
-                    let grandparent: GenericTree =
-                        cur_p.as_ref().unwrap().borrow().clone().parent.clone();
-                    cur_p = grandparent.clone();
+    /// Iterates in ascending order over the keys in both this tree and `other`.
+    pub fn intersection<'a>(
+        &'a self,
+        other: impl IntoIterator<IntoIter = Iter<'a, K, (), B>>,
+    ) -> Intersection<'a, K, B> {
+        Intersection::new(self.iter(), other.into_iter())
+    }
 
-                    // Update tracking variable after x is reassigned
-                    x_is_black = self.check_color(cur_x.clone());
-                    x_is_root = cur_p.is_none();
-                } else {
-                    if child_is_left {
-                        if s_right_is_black {
-                            // Set the left sibling to be black as well
-                            if s_left.is_some() {
-                                s_left.as_ref().unwrap().borrow_mut().color =
-                                    Some(NodeColor::Black);
-                            }
-
-                            // Set s to be red
-                            s.as_ref().unwrap().borrow_mut().color = Some(NodeColor::Red);
-
-                            // Rotate s to the right
-                            Self::rotate_right(s.as_ref().unwrap().clone());
-
-                            // Reassign s to the new right child of x's parent
-                            s = cur_p.as_ref().unwrap().borrow().right.clone();
-                            s_right = s.as_ref().unwrap().borrow().right.clone();
-                            s_left = s.as_ref().unwrap().borrow().left.clone();
-                        }
-                    } else {
-                        if s_left_is_black {
-                            // Set right child to black
-                            if s_right.is_some() {
-                                s_right.as_ref().unwrap().borrow_mut().color =
-                                    Some(NodeColor::Black);
-                            }
-
-                            // Set color of s to red
-                            s.as_ref().unwrap().borrow_mut().color = Some(NodeColor::Red);
-
-                            // Rotate s to the left and then reassign sibling
-                            Self::rotate_left(s.as_ref().unwrap().clone());
-                            s = cur_p.as_ref().unwrap().borrow().left.clone();
-                            s_right = s.as_ref().unwrap().borrow().right.clone();
-                            s_left = s.as_ref().unwrap().borrow().left.clone();
-                        }
-                    }
+    /// Iterates in ascending order over the keys in this tree that are not in `other`.
+    pub fn difference<'a>(
+        &'a self,
+        other: impl IntoIterator<IntoIter = Iter<'a, K, (), B>>,
+    ) -> Difference<'a, K, B> {
+        Difference::new(self.iter(), other.into_iter())
+    }
 
-                    // Set the color of s to the color of x's parent
-                    s.as_ref().unwrap().borrow_mut().color =
-                        cur_p.as_ref().unwrap().borrow().color.clone();
+    /// Iterates in ascending order over the keys in exactly one of this tree and `other`.
+    pub fn symmetric_difference<'a>(
+        &'a self,
+        other: impl IntoIterator<IntoIter = Iter<'a, K, (), B>>,
+    ) -> SymmetricDifference<'a, K, B> {
+        SymmetricDifference::new(self.iter(), other.into_iter())
+    }
 
-                    // Set x's parent color to black
-                    cur_p.as_ref().unwrap().borrow_mut().color = Some(NodeColor::Black);
+    /// Builds the tree of keys in this tree, in `other` or in both, reusing the nodes of both.
+    ///
+    /// With `other` of the same kind this takes O(m log(n / m + 1)) time for trees of m and
+    /// n >= m keys, which beats merging when one tree is much smaller. An AVL tree is
+    /// rebalanced as a red-black tree first, in time linear in its size.
+    /// Keys held by both trees are kept from this one.
+    pub fn into_union(self, other: impl Into<Self>) -> Self {
+        self.combine(other.into(), set_ops::union::<K, B, Self>)
+    }
 
-                    if child_is_left {
-                        // Set the right child of s to black
-                        if s_right.is_some() {
-                            s_right.as_ref().unwrap().borrow_mut().color = Some(NodeColor::Black);
-                        }
+    /// Builds the tree of keys in both this tree and `other`, with the costs of
+    /// [`Self::into_union`] plus dropping the keys that are left out.
+    pub fn into_intersection(self, other: impl Into<Self>) -> Self {
+        self.combine(other.into(), set_ops::intersection::<K, B, Self>)
+    }
 
-                        // Rotate the parent left
-                        Self::rotate_left(cur_p.as_ref().unwrap().clone());
-                    } else {
-                        // Set the left child of sibling to black
-                        if s_left.is_some() {
-                            s_left.as_ref().unwrap().borrow_mut().color = Some(NodeColor::Black);
-                        }
+    /// Builds the tree of keys in this tree that are not in `other`, with the costs of
+    /// [`Self::into_union`] plus dropping the keys that are left out.
+    pub fn into_difference(self, other: impl Into<Self>) -> Self {
+        self.combine(other.into(), set_ops::difference::<K, B, Self>)
+    }
 
-                        // Rotate the parent to the right
-                        Self::rotate_right(cur_p.as_ref().unwrap().clone());
-                    }
+    /// Builds the tree of keys in exactly one of this tree and `other`, with the costs of
+    /// [`Self::into_union`] plus dropping the keys that are left out.
+    pub fn into_symmetric_difference(self, other: impl Into<Self>) -> Self {
+        self.combine(other.into(), set_ops::symmetric_difference::<K, B, Self>)
+    }
+
+    fn combine(self, other: Self, operation: Operation<K, B, Self>) -> Self {
+        RedBlackTree {
+            tree: set_ops::combine::<K, B, Self>(self.tree, other.tree, operation),
+        }
+    }
+
+    // Unlinks `node_to_be_deleted` from the tree, restores the red-black properties and frees
+    // the node
+    pub(crate) fn delete_node<V>(
+        tree: &mut CommonTree<K, V, B>,
+        node_to_be_deleted: Link<K, V, B>,
+    ) -> (K, V) {
+        // Track the parent and children of the node
+        let mut parent = tree.parent(&node_to_be_deleted);
+        let left_child = tree.left(&node_to_be_deleted);
+        let right_child = tree.right(&node_to_be_deleted);
+
+        // Save the color of nodeToBeDeleted
+        let mut u_og_color = tree.color(&node_to_be_deleted);
+
+        // Lowest node whose subtree loses a node
+        let mut resized = parent.clone();
+
+        // If left child is None assign it to x
+        let x = if left_child.is_none() {
+            Self::transplant(tree, Some(node_to_be_deleted.clone()), right_child.clone());
+            right_child.clone()
+        } else if right_child.is_none() {
+            Self::transplant(tree, Some(node_to_be_deleted.clone()), left_child.clone());
+            left_child.clone()
+        } else {
+            let y = Self::find_min(tree, right_child.clone().unwrap());
+            u_og_color = tree.color(&y);
+            let x = tree.right(&y);
+
+            // `x` stays below `y` only when `y` is the right child of the deleted node
+            if tree.parent(&y).as_ref() == Some(&node_to_be_deleted) {
+                parent = Some(y.clone());
+            } else {
+                parent = tree.parent(&y);
+                let y_right = tree.right(&y);
+                Self::transplant(tree, Some(y.clone()), y_right);
+                tree.set_right(&y, tree.right(&node_to_be_deleted));
+                let y_right = tree.right(&y).unwrap();
+                tree.set_parent(&y_right, Some(y.clone()));
+            }
+            resized = parent.clone();
+            Self::transplant(tree, Some(node_to_be_deleted.clone()), Some(y.clone()));
+            tree.set_left(&y, left_child.clone());
+            if let Some(left_child) = &left_child {
+                tree.set_parent(left_child, Some(y.clone()));
+            }
+            tree.set_color(&y, tree.color(&node_to_be_deleted));
+            x
+        };
+        Self::update_sizes_from(tree, resized);
+        if u_og_color == Some(NodeColor::Black) {
+            Self::delete_fix(tree, x, parent);
+        }
+        tree.len -= 1;
+        tree.free_node(node_to_be_deleted)
+    }
+
+    // Restores the red-black properties after a black node was unlinked. `x` took its place
+    // below `parent` and carries an extra black, which moves up the tree until a red node or a
+    // rotation absorbs it
+    fn delete_fix<V>(
+        tree: &mut CommonTree<K, V, B>,
+        x: Option<Link<K, V, B>>,
+        parent: Option<Link<K, V, B>>,
+    ) {
+        let mut x = x;
+        let mut parent = parent;
+        while let Some(p) = parent.clone() {
+            if !Self::check_color(tree, &x) {
+                break;
+            }
+            // `x` may be `None`, but then its sibling is not, since it has a black height of
+            // at least one
+            let is_left = tree.left(&p) == x;
+            let sibling_of = |tree: &CommonTree<K, V, B>| {
+                if is_left {
+                    tree.right(&p)
+                } else {
+                    tree.left(&p)
+                }
+                .expect("Sibling of a doubly black node must exist")
+            };
+            let mut sibling = sibling_of(tree);
+
+            // A red sibling is rotated above the parent, which leaves a black sibling
+            if tree.color(&sibling) == Some(NodeColor::Red) {
+                tree.set_color(&sibling, Some(NodeColor::Black));
+                tree.set_color(&p, Some(NodeColor::Red));
+                if is_left {
+                    Self::rotate_left(tree, p.clone());
+                } else {
+                    Self::rotate_right(tree, p.clone());
+                }
+                sibling = sibling_of(tree);
+            }
+
+            let (near, far) = if is_left {
+                (tree.left(&sibling), tree.right(&sibling))
+            } else {
+                (tree.right(&sibling), tree.left(&sibling))
+            };
+            if Self::check_color(tree, &near) && Self::check_color(tree, &far) {
+                // Making the sibling red moves the extra black up to the parent
+                tree.set_color(&sibling, Some(NodeColor::Red));
+                x = Some(p.clone());
+                parent = tree.parent(&p);
+                continue;
+            }
 
-                    // We are at the root
-                    x_is_root = true;
+            // A red near child is rotated above the sibling, so that the far child is red
+            if Self::check_color(tree, &far) {
+                let near = near.unwrap();
+                tree.set_color(&near, Some(NodeColor::Black));
+                tree.set_color(&sibling, Some(NodeColor::Red));
+                if is_left {
+                    Self::rotate_right(tree, sibling);
+                } else {
+                    Self::rotate_left(tree, sibling);
                 }
+                sibling = near;
+            }
+
+            // Rotating the parent towards `x` gives it the extra black, and the red far child
+            // turns black to make up for the black that the sibling's side loses
+            tree.set_color(&sibling, tree.color(&p));
+            tree.set_color(&p, Some(NodeColor::Black));
+            let far = if is_left {
+                tree.right(&sibling)
+            } else {
+                tree.left(&sibling)
+            };
+            if let Some(far) = far {
+                tree.set_color(&far, Some(NodeColor::Black));
             }
+            if is_left {
+                Self::rotate_left(tree, p);
+            } else {
+                Self::rotate_right(tree, p);
+            }
+            x = tree.root.clone();
+            break;
         }
 
-        // Set x to black once loop breaks in case it is root
-        if let Some(cur_x) = x {
-            cur_x.borrow_mut().color = Some(NodeColor::Black);
+        // A red `x`, or the root, absorbs the extra black
+        if let Some(x) = x {
+            tree.set_color(&x, Some(NodeColor::Black));
         }
     }
 
-    fn transplant(&mut self, node_to_be_deleted: Option<Tree>, child: Option<Tree>) {
+    fn transplant<V>(
+        tree: &mut CommonTree<K, V, B>,
+        node_to_be_deleted: Option<Link<K, V, B>>,
+        child: Option<Link<K, V, B>>,
+    ) {
         let node = match node_to_be_deleted {
-            Some(node) => node.clone(),
+            Some(node) => node,
             None => return,
         };
-        let parent_node = node.borrow().parent.clone();
+        let parent_node = tree.parent(&node);
 
         match parent_node {
             Some(ref parent) => {
-                if parent.borrow().key > node.borrow().key {
-                    parent.borrow_mut().left = child.clone();
+                if tree.left(parent).as_ref() == Some(&node) {
+                    tree.set_left(parent, child.clone());
                 } else {
-                    parent.borrow_mut().right = child.clone();
+                    tree.set_right(parent, child.clone());
                 }
             }
-            None => self.tree.root = child.clone(),
+            None => tree.root = child.clone(),
         }
 
         if let Some(ref child_node) = child {
-            child_node.borrow_mut().parent = parent_node;
+            tree.set_parent(child_node, parent_node);
+        }
+    }
+
+    // Recomputes subtree sizes and heights on the path from `node` up to the root
+    fn update_sizes_from<V>(tree: &mut CommonTree<K, V, B>, mut node: Option<Link<K, V, B>>) {
+        while let Some(current) = node {
+            tree.update_size(&current);
+            node = tree.parent(&current);
         }
     }
 
-    fn find_min(&self, tree: Option<Tree>) -> Option<Tree> {
-        let mut current = tree;
-        while current
-            .as_ref()
-            .map_or(false, |n| n.borrow().left.is_some())
-        {
-            current = current.unwrap().borrow().left.clone();
+    fn find_min<V>(tree: &CommonTree<K, V, B>, node: Link<K, V, B>) -> Link<K, V, B> {
+        let mut current = node;
+        while let Some(left) = tree.left(&current) {
+            current = left;
         }
         current
     }
 
-    fn check_color(&self, tree: Option<Tree>) -> bool {
-        tree.as_ref()
-            .map_or(true, |node| node.borrow().color == Some(NodeColor::Black))
+    fn check_color<V>(tree: &CommonTree<K, V, B>, node: &Option<Link<K, V, B>>) -> bool {
+        node.as_ref()
+            .is_none_or(|node| tree.color(node) == Some(NodeColor::Black))
+    }
+}
+
+impl<'a, K: Ord, B: Backend> IntoIterator for &'a RedBlackTree<K, B> {
+    type Item = &'a K;
+    type IntoIter = Iter<'a, K, (), B>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<K: Ord, B: Backend> IntoIterator for RedBlackTree<K, B> {
+    type Item = K;
+    type IntoIter = IntoIter<K, (), B>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter::new(self.tree)
+    }
+}
+
+impl<K: Ord, B: Backend> From<AVLTree<K, B>> for RedBlackTree<K, B> {
+    /// Relinks the nodes of an AVL tree into a balanced red-black tree, in O(n).
+    fn from(tree: AVLTree<K, B>) -> Self {
+        let mut tree = tree.tree;
+        tree.rebuild_balanced(true);
+        RedBlackTree { tree }
+    }
+}
+
+impl<K: Ord, B: Backend> Join<K, B> for RedBlackTree<K, B> {
+    type Subtree = Subtree<K, (), B>;
+
+    fn subtree(tree: &CommonTree<K, (), B>, root: Option<Link<K, (), B>>) -> Self::Subtree {
+        let black_height = Self::black_height(tree, &root);
+        (root, black_height)
+    }
+
+    fn into_root(
+        tree: &mut CommonTree<K, (), B>,
+        (root, _): Self::Subtree,
+    ) -> Option<Link<K, (), B>> {
+        if let Some(root) = &root {
+            tree.set_parent(root, None);
+            tree.set_color(root, Some(NodeColor::Black));
+        }
+        root
+    }
+
+    fn is_empty((root, _): &Self::Subtree) -> bool {
+        root.is_none()
+    }
+
+    fn expose(
+        tree: &mut CommonTree<K, (), B>,
+        (root, black_height): Self::Subtree,
+    ) -> (Self::Subtree, Link<K, (), B>, Self::Subtree) {
+        let node = root.expect("Exposed subtree must not be empty");
+        let child_height = Self::child_black_height(tree, &node, black_height);
+        let (left, right) = (tree.left(&node), tree.right(&node));
+        Self::unlink_children(tree, &node);
+        ((left, child_height), node, (right, child_height))
+    }
+
+    fn join(
+        tree: &mut CommonTree<K, (), B>,
+        left: Self::Subtree,
+        pivot: Link<K, (), B>,
+        right: Self::Subtree,
+    ) -> Self::Subtree {
+        let (root, black_height) = Self::join_links(tree, left, pivot, right);
+        (Some(root), black_height)
+    }
+
+    fn split(
+        tree: &mut CommonTree<K, (), B>,
+        subtree: Self::Subtree,
+        pivot: &Link<K, (), B>,
+    ) -> Split<K, (), B> {
+        Self::split_links(tree, subtree, &|tree, node| {
+            tree.key(pivot).cmp(tree.key(node))
+        })
+    }
+}
+
+impl<K: Ord, B: Backend> FromIterator<K> for RedBlackTree<K, B> {
+    /// Sorts the keys and builds the tree in one pass. Of equal keys, the first one is kept.
+    fn from_iter<I: IntoIterator<Item = K>>(iter: I) -> Self {
+        let mut keys: Vec<K> = iter.into_iter().collect();
+        keys.sort();
+        keys.dedup();
+        Self::from_sorted_iter(keys).expect("sorted and deduplicated keys are ascending")
+    }
+}
+
+impl<K: Ord, B: Backend> Extend<K> for RedBlackTree<K, B> {
+    /// Builds a tree of the new keys and unites it with this one, keeping the keys already
+    /// present.
+    fn extend<I: IntoIterator<Item = K>>(&mut self, iter: I) {
+        let keys: Self = iter.into_iter().collect();
+        *self = std::mem::take(self).into_union(keys);
+    }
+}
+
+impl<'a, K: Ord + Copy + 'a, B: Backend> Extend<&'a K> for RedBlackTree<K, B> {
+    fn extend<I: IntoIterator<Item = &'a K>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().copied());
+    }
+}
+
+impl<K: Ord + Clone, B: Backend> OrderedSet<K> for RedBlackTree<K, B> {
+    fn insert(&mut self, key: K) -> bool {
+        RedBlackTree::insert(self, key)
+    }
+
+    fn remove(&mut self, key: &K) -> Option<K> {
+        self.delete(key)
+    }
+
+    fn contains(&self, key: &K) -> bool {
+        RedBlackTree::contains(self, key)
+    }
+
+    fn len(&self) -> usize {
+        RedBlackTree::len(self)
+    }
+
+    fn is_empty(&self) -> bool {
+        RedBlackTree::is_empty(self)
+    }
+
+    fn height(&self) -> i32 {
+        RedBlackTree::height(self)
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = &K> + '_> {
+        Box::new(RedBlackTree::iter(self))
+    }
+
+    fn range(&self, start: Bound<&K>, end: Bound<&K>) -> Box<dyn Iterator<Item = &K> + '_> {
+        Box::new(RedBlackTree::range(self, (start, end)))
+    }
+
+    fn min(&self) -> Option<K> {
+        self.tree.min()
+    }
+
+    fn max(&self) -> Option<K> {
+        self.tree.max()
+    }
+
+    fn inorder(&self) -> Vec<K> {
+        self.tree.print_inorder()
+    }
+
+    fn preorder(&self) -> Vec<K> {
+        self.tree.print_preorder()
+    }
+
+    fn postorder(&self) -> Vec<K> {
+        self.tree.print_postorder()
+    }
+
+    fn count_leaves(&self) -> i32 {
+        self.tree.count_leaves()
+    }
+
+    fn print_structure(&self)
+    where
+        K: Display,
+    {
+        self.tree.print_structure()
+    }
+}
+
+impl<K: Ord, V, B: Backend> Default for RedBlackMap<K, V, B> {
+    fn default() -> Self {
+        RedBlackMap {
+            tree: CommonTree::default(),
+        }
+    }
+}
+
+impl<K: Ord, V> RedBlackMap<K, V> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<K: Ord, V, B: Backend> RedBlackMap<K, V, B> {
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    /// Inserts a key-value pair, returning the previous value if the key was present.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        match self.entry(key) {
+            Entry::Occupied(mut entry) => Some(entry.insert(value)),
+            Entry::Vacant(entry) => {
+                entry.insert(value);
+                None
+            }
+        }
+    }
+
+    /// Looks up `key` once, for inserting it or updating its value without another search.
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V, B> {
+        Entry::new(&mut self.tree, key, RedBlackTree::attach)
+    }
+
+    /// Returns a cursor at the smallest key, or past the ends if the tree is empty.
+    pub fn cursor_front(&self) -> Cursor<'_, K, V, B> {
+        Cursor::new(&self.tree, self.tree.first_node())
+    }
+
+    /// Returns a cursor at the largest key, or past the ends if the tree is empty.
+    pub fn cursor_back(&self) -> Cursor<'_, K, V, B> {
+        Cursor::new(&self.tree, self.tree.last_node())
+    }
+
+    /// Returns a cursor at the smallest key within `bound`, or past the ends if there is none.
+    pub fn lower_bound(&self, bound: Bound<&K>) -> Cursor<'_, K, V, B> {
+        Cursor::new(&self.tree, self.tree.first_node_after(bound))
+    }
+
+    pub fn cursor_front_mut(&mut self) -> CursorMut<'_, K, V, B> {
+        let first = self.tree.first_node();
+        CursorMut::new(
+            &mut self.tree,
+            first,
+            RedBlackTree::attach,
+            RedBlackTree::delete_node,
+        )
+    }
+
+    pub fn cursor_back_mut(&mut self) -> CursorMut<'_, K, V, B> {
+        let last = self.tree.last_node();
+        CursorMut::new(
+            &mut self.tree,
+            last,
+            RedBlackTree::attach,
+            RedBlackTree::delete_node,
+        )
+    }
+
+    pub fn lower_bound_mut(&mut self, bound: Bound<&K>) -> CursorMut<'_, K, V, B> {
+        let node = self.tree.first_node_after(bound);
+        CursorMut::new(
+            &mut self.tree,
+            node,
+            RedBlackTree::attach,
+            RedBlackTree::delete_node,
+        )
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        let node = self.tree.search(key)?;
+        Some(self.tree.value(&node))
+    }
+
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        let node = self.tree.search(key)?;
+        Some(self.tree.value_mut(&node))
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.tree.search(key).is_some()
+    }
+
+    /// Removes a key from the map, returning its value if the key was present.
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let node = self.tree.search(key)?;
+        Some(RedBlackTree::delete_node(&mut self.tree, node).1)
+    }
+}
+
+impl<K: Ord, V, A: Augment<K, V>, B: Backend> Default for RedBlackAugmentedMap<K, V, A, B> {
+    fn default() -> Self {
+        RedBlackAugmentedMap {
+            tree: augment::tree::<K, V, A, B>(),
+        }
+    }
+}
+
+impl<K: Ord, V, A: Augment<K, V>> RedBlackAugmentedMap<K, V, A> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<K: Ord, V, A: Augment<K, V>, B: Backend> RedBlackAugmentedMap<K, V, A, B> {
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    /// Inserts a key-value pair, returning the previous value if the key was present.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        augment::insert::<K, V, A, B>(&mut self.tree, key, value, RedBlackTree::attach)
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        let node = self.tree.search(key)?;
+        Some(&self.tree.value(&node).0)
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.tree.search(key).is_some()
+    }
+
+    /// Removes a key from the map, returning its value if the key was present.
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let node = self.tree.search(key)?;
+        Some(RedBlackTree::delete_node(&mut self.tree, node).1 .0)
+    }
+
+    /// Combines the summaries of all entries in key order, in O(1).
+    pub fn fold(&self) -> A::Summary {
+        augment::summary::<K, V, A, B>(&self.tree, &self.tree.root)
+    }
+
+    /// Combines the summaries of the entries whose keys fall within `range` in key order, in
+    /// O(log n).
+    pub fn fold_range<R: RangeBounds<K>>(&self, range: R) -> A::Summary {
+        augment::fold_range::<K, V, A, B, R>(&self.tree, range)
+    }
+}
+
+impl<K: Ord, B: Backend> Default for RedBlackMultiset<K, B> {
+    fn default() -> Self {
+        RedBlackMultiset {
+            tree: CommonTree::default(),
+            len: 0,
+        }
+    }
+}
+
+impl<K: Ord> RedBlackMultiset<K> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<K: Ord, B: Backend> RedBlackMultiset<K, B> {
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of keys, counting every copy.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns how many copies of `key` the multiset holds.
+    pub fn count(&self, key: &K) -> usize {
+        self.tree
+            .search(key)
+            .map_or(0, |node| *self.tree.value(&node))
+    }
+
+    pub fn contains(&self, key: &K) -> bool {
+        self.tree.search(key).is_some()
+    }
+
+    /// Adds one copy of `key`.
+    pub fn insert(&mut self, key: K) {
+        *Entry::new(&mut self.tree, key, RedBlackTree::attach).or_insert(0) += 1;
+        self.len += 1;
+    }
+
+    /// Removes one copy of `key`, returning whether there was one.
+    pub fn remove_one(&mut self, key: &K) -> bool {
+        let Some(node) = self.tree.search(key) else {
+            return false;
+        };
+        if *self.tree.value(&node) > 1 {
+            *self.tree.value_mut(&node) -= 1;
+        } else {
+            RedBlackTree::delete_node(&mut self.tree, node);
+        }
+        self.len -= 1;
+        true
+    }
+
+    /// Removes every copy of `key`, returning how many there were.
+    pub fn remove_all(&mut self, key: &K) -> usize {
+        let Some(node) = self.tree.search(key) else {
+            return 0;
+        };
+        let count = RedBlackTree::delete_node(&mut self.tree, node).1;
+        self.len -= count;
+        count
+    }
+
+    /// Iterates over the keys in ascending order, repeating each key for every copy.
+    pub fn iter(&self) -> MultiIter<'_, K, B> {
+        MultiIter::new(&self.tree, self.len)
+    }
+}
+
+impl<'a, K: Ord, B: Backend> IntoIterator for &'a RedBlackMultiset<K, B> {
+    type Item = &'a K;
+    type IntoIter = MultiIter<'a, K, B>;
+
+    fn into_iter(self) -> MultiIter<'a, K, B> {
+        self.iter()
+    }
+}
+
+impl<K: Ord + Clone, B: Backend> IntoIterator for RedBlackMultiset<K, B> {
+    type Item = K;
+    type IntoIter = MultiIntoIter<K, B>;
+
+    fn into_iter(self) -> MultiIntoIter<K, B> {
+        MultiIntoIter::new(self.tree, self.len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RedBlackTree;
+    use crate::common_tree::NodeColor;
+    use crate::error::{Invariant, InvariantViolation};
+
+    fn violation(key: i32, invariant: Invariant) -> InvariantViolation<i32> {
+        InvariantViolation { key, invariant }
+    }
+
+    #[test]
+    fn broken_red_black_properties_are_reported() {
+        let mut tree = RedBlackTree::new();
+        for key in 1..=3 {
+            tree.insert(key);
+        }
+        let nodes = &mut tree.tree;
+        let root = nodes.root.clone().unwrap();
+        let (left, right) = (nodes.left(&root).unwrap(), nodes.right(&root).unwrap());
+        nodes.set_height(&right, 3);
+        assert_eq!(tree.validate(), Err(vec![violation(3, Invariant::Height)]));
+
+        let nodes = &mut tree.tree;
+        nodes.set_height(&right, 1);
+        nodes.set_color(&root, Some(NodeColor::Red));
+        assert_eq!(
+            tree.validate(),
+            Err(vec![
+                violation(2, Invariant::BlackRoot),
+                violation(1, Invariant::RedRed),
+                violation(3, Invariant::RedRed),
+            ])
+        );
+
+        let nodes = &mut tree.tree;
+        nodes.set_color(&root, Some(NodeColor::Black));
+        nodes.set_color(&left, Some(NodeColor::Black));
+        nodes.set_color(&right, None);
+        nodes.set_parent(&right, None);
+        nodes.set_size(&root, 4);
+        let violations = tree.validate().unwrap_err();
+        assert_eq!(
+            violations,
+            vec![
+                violation(2, Invariant::Size),
+                violation(3, Invariant::Parent),
+                violation(3, Invariant::Color),
+            ]
+        );
+        assert_eq!(violations[1].to_string(), "node 3: parent link is wrong");
     }
 }
@@ -0,0 +1,397 @@
+use crate::common_tree::CommonTree;
+use crate::iter::Iter;
+use crate::storage::{Backend, Link, RcBackend};
+use std::cmp::{max, min, Ordering};
+use std::iter::{FusedIterator, Peekable};
+
+/// The splitting and joining primitives of a balancing scheme, on which the set operations
+/// below are built. Every subtree passed around is detached and lives in the store of `tree`.
+pub(crate) trait Join<K: Ord, B: Backend> {
+    /// A detached subtree, with whatever the scheme needs to know about it to join it.
+    type Subtree;
+
+    fn subtree(tree: &CommonTree<K, (), B>, root: Option<Link<K, (), B>>) -> Self::Subtree;
+
+    /// Turns a subtree back into the root of a whole tree.
+    fn into_root(tree: &mut CommonTree<K, (), B>, subtree: Self::Subtree)
+        -> Option<Link<K, (), B>>;
+
+    fn is_empty(subtree: &Self::Subtree) -> bool;
+
+    /// Unlinks the root of a non-empty subtree from its two children.
+    fn expose(
+        tree: &mut CommonTree<K, (), B>,
+        subtree: Self::Subtree,
+    ) -> (Self::Subtree, Link<K, (), B>, Self::Subtree);
+
+    fn join(
+        tree: &mut CommonTree<K, (), B>,
+        left: Self::Subtree,
+        pivot: Link<K, (), B>,
+        right: Self::Subtree,
+    ) -> Self::Subtree;
+
+    /// Splits a subtree around the key of `pivot`, a node outside of it, into the keys before
+    /// that key, the node holding it if there is one, and the keys after it.
+    fn split(
+        tree: &mut CommonTree<K, (), B>,
+        subtree: Self::Subtree,
+        pivot: &Link<K, (), B>,
+    ) -> Parts<K, B, Self>;
+}
+
+// The pieces a subtree is split into
+type Parts<K, B, T> = (
+    <T as Join<K, B>>::Subtree,
+    Option<Link<K, (), B>>,
+    <T as Join<K, B>>::Subtree,
+);
+
+// One of the operations below, applied to the two subtrees of a tree
+pub(crate) type Operation<K, B, T> = fn(
+    &mut CommonTree<K, (), B>,
+    <T as Join<K, B>>::Subtree,
+    <T as Join<K, B>>::Subtree,
+) -> <T as Join<K, B>>::Subtree;
+
+// Applies a set operation to two whole trees. The smaller tree is moved into the storage of
+// the larger one first, so that both subtrees can be split and joined within one store.
+pub(crate) fn combine<K: Ord, B: Backend, T: Join<K, B>>(
+    mut left: CommonTree<K, (), B>,
+    mut right: CommonTree<K, (), B>,
+    operation: Operation<K, B, T>,
+) -> CommonTree<K, (), B> {
+    let (mut tree, left_root, right_root) = if left.len >= right.len {
+        let right_root = right.root.take();
+        let right_root = left.adopt(&mut right, right_root);
+        let left_root = left.root.take();
+        (left, left_root, right_root)
+    } else {
+        let left_root = left.root.take();
+        let left_root = right.adopt(&mut left, left_root);
+        let right_root = right.root.take();
+        (right, left_root, right_root)
+    };
+    let left = T::subtree(&tree, left_root);
+    let right = T::subtree(&tree, right_root);
+    let result = operation(&mut tree, left, right);
+    tree.root = T::into_root(&mut tree, result);
+    tree.len = tree.subtree_size(&tree.root);
+    tree
+}
+
+// The operations below follow Blelloch, Ferizovic and Sun, "Just Join for Parallel Ordered
+// Sets": the root of `right` splits `left`, and the halves are combined recursively and joined
+// back around it. On trees of sizes m <= n this takes O(m log(n / m + 1)) time, apart from
+// dropping the keys that do not make it into the result. Where both trees hold a key, the node
+// from `left` is kept.
+
+pub(crate) fn union<K: Ord, B: Backend, T: Join<K, B>>(
+    tree: &mut CommonTree<K, (), B>,
+    left: T::Subtree,
+    right: T::Subtree,
+) -> T::Subtree {
+    if T::is_empty(&left) {
+        return right;
+    }
+    if T::is_empty(&right) {
+        return left;
+    }
+    let (right_left, pivot, right_right) = T::expose(tree, right);
+    let (left_left, found, left_right) = T::split(tree, left, &pivot);
+    let pivot = match found {
+        Some(found) => {
+            tree.free_node(pivot);
+            found
+        }
+        None => pivot,
+    };
+    let smaller = union::<K, B, T>(tree, left_left, right_left);
+    let larger = union::<K, B, T>(tree, left_right, right_right);
+    T::join(tree, smaller, pivot, larger)
+}
+
+pub(crate) fn intersection<K: Ord, B: Backend, T: Join<K, B>>(
+    tree: &mut CommonTree<K, (), B>,
+    left: T::Subtree,
+    right: T::Subtree,
+) -> T::Subtree {
+    if T::is_empty(&left) || T::is_empty(&right) {
+        free_subtree::<K, B, T>(tree, left);
+        free_subtree::<K, B, T>(tree, right);
+        return T::subtree(tree, None);
+    }
+    let (right_left, pivot, right_right) = T::expose(tree, right);
+    let (left_left, found, left_right) = T::split(tree, left, &pivot);
+    let smaller = intersection::<K, B, T>(tree, left_left, right_left);
+    let larger = intersection::<K, B, T>(tree, left_right, right_right);
+    match found {
+        Some(found) => {
+            tree.free_node(pivot);
+            T::join(tree, smaller, found, larger)
+        }
+        None => {
+            tree.free_node(pivot);
+            join_pair::<K, B, T>(tree, smaller, larger)
+        }
+    }
+}
+
+pub(crate) fn difference<K: Ord, B: Backend, T: Join<K, B>>(
+    tree: &mut CommonTree<K, (), B>,
+    left: T::Subtree,
+    right: T::Subtree,
+) -> T::Subtree {
+    if T::is_empty(&left) || T::is_empty(&right) {
+        free_subtree::<K, B, T>(tree, right);
+        return left;
+    }
+    let (right_left, pivot, right_right) = T::expose(tree, right);
+    let (left_left, found, left_right) = T::split(tree, left, &pivot);
+    tree.free_node(pivot);
+    if let Some(found) = found {
+        tree.free_node(found);
+    }
+    let smaller = difference::<K, B, T>(tree, left_left, right_left);
+    let larger = difference::<K, B, T>(tree, left_right, right_right);
+    join_pair::<K, B, T>(tree, smaller, larger)
+}
+
+pub(crate) fn symmetric_difference<K: Ord, B: Backend, T: Join<K, B>>(
+    tree: &mut CommonTree<K, (), B>,
+    left: T::Subtree,
+    right: T::Subtree,
+) -> T::Subtree {
+    if T::is_empty(&left) {
+        return right;
+    }
+    if T::is_empty(&right) {
+        return left;
+    }
+    let (right_left, pivot, right_right) = T::expose(tree, right);
+    let (left_left, found, left_right) = T::split(tree, left, &pivot);
+    let smaller = symmetric_difference::<K, B, T>(tree, left_left, right_left);
+    let larger = symmetric_difference::<K, B, T>(tree, left_right, right_right);
+    match found {
+        Some(found) => {
+            tree.free_node(found);
+            tree.free_node(pivot);
+            join_pair::<K, B, T>(tree, smaller, larger)
+        }
+        None => T::join(tree, smaller, pivot, larger),
+    }
+}
+
+// Joins two subtrees without a pivot, by taking the largest node of `left` as the pivot
+fn join_pair<K: Ord, B: Backend, T: Join<K, B>>(
+    tree: &mut CommonTree<K, (), B>,
+    left: T::Subtree,
+    right: T::Subtree,
+) -> T::Subtree {
+    if T::is_empty(&left) {
+        return right;
+    }
+    let (left, last) = split_last::<K, B, T>(tree, left);
+    T::join(tree, left, last, right)
+}
+
+fn split_last<K: Ord, B: Backend, T: Join<K, B>>(
+    tree: &mut CommonTree<K, (), B>,
+    subtree: T::Subtree,
+) -> (T::Subtree, Link<K, (), B>) {
+    let (left, node, right) = T::expose(tree, subtree);
+    if T::is_empty(&right) {
+        return (left, node);
+    }
+    let (right, last) = split_last::<K, B, T>(tree, right);
+    (T::join(tree, left, node, right), last)
+}
+
+fn free_subtree<K: Ord, B: Backend, T: Join<K, B>>(
+    tree: &mut CommonTree<K, (), B>,
+    subtree: T::Subtree,
+) {
+    if T::is_empty(&subtree) {
+        return;
+    }
+    let (left, node, right) = T::expose(tree, subtree);
+    free_subtree::<K, B, T>(tree, left);
+    free_subtree::<K, B, T>(tree, right);
+    tree.free_node(node);
+}
+
+/// Lazy iterator over the keys in either of two trees, in ascending order.
+pub struct Union<'a, K, B: Backend = RcBackend> {
+    left: Peekable<Iter<'a, K, (), B>>,
+    right: Peekable<Iter<'a, K, (), B>>,
+}
+
+/// Lazy iterator over the keys in both of two trees, in ascending order.
+pub struct Intersection<'a, K, B: Backend = RcBackend> {
+    left: Iter<'a, K, (), B>,
+    right: Peekable<Iter<'a, K, (), B>>,
+}
+
+/// Lazy iterator over the keys in the first of two trees but not in the second, in ascending
+/// order.
+pub struct Difference<'a, K, B: Backend = RcBackend> {
+    left: Iter<'a, K, (), B>,
+    right: Peekable<Iter<'a, K, (), B>>,
+}
+
+/// Lazy iterator over the keys in exactly one of two trees, in ascending order.
+pub struct SymmetricDifference<'a, K, B: Backend = RcBackend> {
+    left: Peekable<Iter<'a, K, (), B>>,
+    right: Peekable<Iter<'a, K, (), B>>,
+}
+
+impl<'a, K, B: Backend> Union<'a, K, B> {
+    pub(crate) fn new(left: Iter<'a, K, (), B>, right: Iter<'a, K, (), B>) -> Self {
+        Union {
+            left: left.peekable(),
+            right: right.peekable(),
+        }
+    }
+}
+
+impl<'a, K, B: Backend> Intersection<'a, K, B> {
+    pub(crate) fn new(left: Iter<'a, K, (), B>, right: Iter<'a, K, (), B>) -> Self {
+        Intersection {
+            left,
+            right: right.peekable(),
+        }
+    }
+}
+
+impl<'a, K, B: Backend> Difference<'a, K, B> {
+    pub(crate) fn new(left: Iter<'a, K, (), B>, right: Iter<'a, K, (), B>) -> Self {
+        Difference {
+            left,
+            right: right.peekable(),
+        }
+    }
+}
+
+impl<'a, K, B: Backend> SymmetricDifference<'a, K, B> {
+    pub(crate) fn new(left: Iter<'a, K, (), B>, right: Iter<'a, K, (), B>) -> Self {
+        SymmetricDifference {
+            left: left.peekable(),
+            right: right.peekable(),
+        }
+    }
+}
+
+// Skips the keys of `iter` that are smaller than `key` and reports how the next one compares
+fn skip_below<'a, K: Ord + 'a>(
+    iter: &mut Peekable<impl Iterator<Item = &'a K>>,
+    key: &K,
+) -> Option<Ordering> {
+    while let Some(next) = iter.peek() {
+        match (*next).cmp(key) {
+            Ordering::Less => {
+                iter.next();
+            }
+            ordering => return Some(ordering),
+        }
+    }
+    None
+}
+
+impl<'a, K: Ord, B: Backend> Iterator for Union<'a, K, B> {
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<&'a K> {
+        match (self.left.peek(), self.right.peek()) {
+            (Some(left), Some(right)) => match left.cmp(right) {
+                Ordering::Less => self.left.next(),
+                Ordering::Greater => self.right.next(),
+                Ordering::Equal => {
+                    self.right.next();
+                    self.left.next()
+                }
+            },
+            (Some(_), None) => self.left.next(),
+            (None, _) => self.right.next(),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (left, right) = (self.left.len(), self.right.len());
+        (max(left, right), Some(left + right))
+    }
+}
+
+impl<'a, K: Ord, B: Backend> Iterator for Intersection<'a, K, B> {
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<&'a K> {
+        for key in self.left.by_ref() {
+            match skip_below(&mut self.right, key)? {
+                Ordering::Equal => {
+                    self.right.next();
+                    return Some(key);
+                }
+                _ => continue,
+            }
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(min(self.left.len(), self.right.len())))
+    }
+}
+
+impl<'a, K: Ord, B: Backend> Iterator for Difference<'a, K, B> {
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<&'a K> {
+        for key in self.left.by_ref() {
+            match skip_below(&mut self.right, key) {
+                Some(Ordering::Equal) => {
+                    self.right.next();
+                }
+                _ => return Some(key),
+            }
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (left, right) = (self.left.len(), self.right.len());
+        (left.saturating_sub(right), Some(left))
+    }
+}
+
+impl<'a, K: Ord, B: Backend> Iterator for SymmetricDifference<'a, K, B> {
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<&'a K> {
+        loop {
+            match (self.left.peek(), self.right.peek()) {
+                (Some(left), Some(right)) => match left.cmp(right) {
+                    Ordering::Less => return self.left.next(),
+                    Ordering::Greater => return self.right.next(),
+                    Ordering::Equal => {
+                        self.left.next();
+                        self.right.next();
+                    }
+                },
+                (Some(_), None) => return self.left.next(),
+                (None, _) => return self.right.next(),
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.left.len() + self.right.len()))
+    }
+}
+
+impl<K: Ord, B: Backend> FusedIterator for Union<'_, K, B> {}
+
+impl<K: Ord, B: Backend> FusedIterator for Intersection<'_, K, B> {}
+
+impl<K: Ord, B: Backend> FusedIterator for Difference<'_, K, B> {}
+
+impl<K: Ord, B: Backend> FusedIterator for SymmetricDifference<'_, K, B> {}
@@ -0,0 +1,216 @@
+use crate::common_tree::NodeColor;
+use std::cell::RefCell;
+use std::marker::PhantomData;
+use std::rc::{Rc, Weak};
+
+/// Selects how the nodes of a tree are stored: [`RcBackend`] or
+/// [`ArenaBackend`](crate::ArenaBackend). The balancing code only reaches its nodes through the
+/// storage of the backend, so the same algorithms run on every backend.
+///
+/// This trait is sealed. The storage behind it is private to the crate, so that the nodes can
+/// only be changed by the trees themselves.
+pub trait Backend: Sealed {}
+
+// Carries the storage of a backend, out of reach of other crates
+pub trait Sealed {
+    type Store<K, V>: Storage<K, V>;
+}
+
+/// Handle to a node of a tree stored in backend `B`.
+pub type Link<K, V = (), B = RcBackend> = <<B as Sealed>::Store<K, V> as Storage<K, V>>::Link;
+
+/// Owns or tracks the nodes of one tree.
+///
+/// # Safety
+///
+/// Every method that takes a link is `unsafe`: the link must point at a live node of the tree
+/// that owns this store, and the store must not be used to change a node while a reference
+/// returned by [`Storage::key`] or [`Storage::value`] is alive.
+#[allow(clippy::missing_safety_doc)]
+pub trait Storage<K, V>: Default {
+    /// Handles compare by identity, not by key.
+    type Link: Clone + PartialEq;
+
+    /// Allocates an unlinked node with a height and size of 1 and no color.
+    fn alloc(&mut self, key: K, value: V) -> Self::Link;
+
+    /// Releases a node that has been unlinked from the tree, handing back its contents.
+    unsafe fn free(&mut self, node: Self::Link) -> (K, V);
+
+    /// Moves the subtree under `node` out of `other` into this store, returning the new link
+    /// to its root, whose parent is left unset. This is free for stores whose nodes own each
+    /// other, and costs O(size of the subtree) for stores that own their nodes.
+    unsafe fn adopt(&mut self, other: &mut Self, node: Self::Link) -> Self::Link;
+    unsafe fn left(&self, node: &Self::Link) -> Option<Self::Link>;
+    unsafe fn right(&self, node: &Self::Link) -> Option<Self::Link>;
+    unsafe fn parent(&self, node: &Self::Link) -> Option<Self::Link>;
+    unsafe fn set_left(&mut self, node: &Self::Link, child: Option<Self::Link>);
+    unsafe fn set_right(&mut self, node: &Self::Link, child: Option<Self::Link>);
+    unsafe fn set_parent(&mut self, node: &Self::Link, parent: Option<Self::Link>);
+    unsafe fn height(&self, node: &Self::Link) -> i32;
+    unsafe fn set_height(&mut self, node: &Self::Link, height: i32);
+    unsafe fn size(&self, node: &Self::Link) -> usize;
+    unsafe fn set_size(&mut self, node: &Self::Link, size: usize);
+    unsafe fn color(&self, node: &Self::Link) -> Option<NodeColor>;
+    unsafe fn set_color(&mut self, node: &Self::Link, color: Option<NodeColor>);
+    unsafe fn key(&self, node: &Self::Link) -> &K;
+    unsafe fn value(&self, node: &Self::Link) -> &V;
+    unsafe fn value_mut(&mut self, node: &Self::Link) -> &mut V;
+}
+
+pub(crate) type Tree<K, V = ()> = Rc<RefCell<Node<K, V>>>;
+pub(crate) type GenericTree<K, V = ()> = Option<Tree<K, V>>;
+/// Non-owning link from a node back to its parent, so that parent and child do not keep
+/// each other alive.
+pub(crate) type WeakTree<K, V = ()> = Weak<RefCell<Node<K, V>>>;
+
+#[derive(Debug)]
+pub(crate) struct Node<K, V = ()> {
+    pub(crate) key: K,
+    pub(crate) value: V,
+    pub(crate) left: GenericTree<K, V>,
+    pub(crate) right: GenericTree<K, V>,
+    pub(crate) parent: Option<WeakTree<K, V>>,
+    pub(crate) height: i32,
+    /// Number of nodes in the subtree rooted here, kept up to date for order statistics.
+    pub(crate) size: usize,
+    pub(crate) color: Option<NodeColor>,
+}
+
+impl<K, V> Node<K, V> {
+    pub(crate) fn parent(&self) -> GenericTree<K, V> {
+        self.parent.as_ref().and_then(Weak::upgrade)
+    }
+}
+
+/// Stores every node in its own `Rc<RefCell<Node>>`, with weak links back to the parent.
+pub struct RcBackend;
+
+impl Backend for RcBackend {}
+
+impl Sealed for RcBackend {
+    type Store<K, V> = RcStore<K, V>;
+}
+
+/// The nodes of an [`RcBackend`] tree own each other, so the store itself holds nothing.
+pub struct RcStore<K, V>(PhantomData<Tree<K, V>>);
+
+impl<K, V> Default for RcStore<K, V> {
+    fn default() -> Self {
+        RcStore(PhantomData)
+    }
+}
+
+/// A node of an [`RcBackend`] tree.
+pub struct RcLink<K, V>(Tree<K, V>);
+
+impl<K, V> Clone for RcLink<K, V> {
+    fn clone(&self) -> Self {
+        RcLink(Rc::clone(&self.0))
+    }
+}
+
+impl<K, V> PartialEq for RcLink<K, V> {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+fn wrap<K, V>(node: &GenericTree<K, V>) -> Option<RcLink<K, V>> {
+    node.clone().map(RcLink)
+}
+
+impl<K, V> Storage<K, V> for RcStore<K, V> {
+    type Link = RcLink<K, V>;
+
+    fn alloc(&mut self, key: K, value: V) -> RcLink<K, V> {
+        RcLink(Rc::new(RefCell::new(Node {
+            key,
+            value,
+            left: None,
+            right: None,
+            parent: None,
+            height: 1,
+            size: 1,
+            color: None,
+        })))
+    }
+
+    unsafe fn free(&mut self, node: RcLink<K, V>) -> (K, V) {
+        match Rc::try_unwrap(node.0) {
+            Ok(node) => {
+                let node = node.into_inner();
+                (node.key, node.value)
+            }
+            Err(_) => panic!("Removed node is still referenced"),
+        }
+    }
+
+    unsafe fn adopt(&mut self, _other: &mut Self, node: RcLink<K, V>) -> RcLink<K, V> {
+        node.0.borrow_mut().parent = None;
+        node
+    }
+
+    unsafe fn left(&self, node: &RcLink<K, V>) -> Option<RcLink<K, V>> {
+        wrap(&node.0.borrow().left)
+    }
+
+    unsafe fn right(&self, node: &RcLink<K, V>) -> Option<RcLink<K, V>> {
+        wrap(&node.0.borrow().right)
+    }
+
+    unsafe fn parent(&self, node: &RcLink<K, V>) -> Option<RcLink<K, V>> {
+        node.0.borrow().parent().map(RcLink)
+    }
+
+    unsafe fn set_left(&mut self, node: &RcLink<K, V>, child: Option<RcLink<K, V>>) {
+        node.0.borrow_mut().left = child.map(|child| child.0);
+    }
+
+    unsafe fn set_right(&mut self, node: &RcLink<K, V>, child: Option<RcLink<K, V>>) {
+        node.0.borrow_mut().right = child.map(|child| child.0);
+    }
+
+    unsafe fn set_parent(&mut self, node: &RcLink<K, V>, parent: Option<RcLink<K, V>>) {
+        node.0.borrow_mut().parent = parent.map(|parent| Rc::downgrade(&parent.0));
+    }
+
+    unsafe fn height(&self, node: &RcLink<K, V>) -> i32 {
+        node.0.borrow().height
+    }
+
+    unsafe fn set_height(&mut self, node: &RcLink<K, V>, height: i32) {
+        node.0.borrow_mut().height = height;
+    }
+
+    unsafe fn size(&self, node: &RcLink<K, V>) -> usize {
+        node.0.borrow().size
+    }
+
+    unsafe fn set_size(&mut self, node: &RcLink<K, V>, size: usize) {
+        node.0.borrow_mut().size = size;
+    }
+
+    unsafe fn color(&self, node: &RcLink<K, V>) -> Option<NodeColor> {
+        node.0.borrow().color.clone()
+    }
+
+    unsafe fn set_color(&mut self, node: &RcLink<K, V>, color: Option<NodeColor>) {
+        node.0.borrow_mut().color = color;
+    }
+
+    // The `RefCell` guards are bypassed for keys and values so that references can outlive a
+    // single call. All mutation goes through `&mut self`, which the caller cannot do while the
+    // references are borrowed from `self`.
+    unsafe fn key(&self, node: &RcLink<K, V>) -> &K {
+        unsafe { &(*node.0.as_ptr()).key }
+    }
+
+    unsafe fn value(&self, node: &RcLink<K, V>) -> &V {
+        unsafe { &(*node.0.as_ptr()).value }
+    }
+
+    unsafe fn value_mut(&mut self, node: &RcLink<K, V>) -> &mut V {
+        unsafe { &mut (*node.0.as_ptr()).value }
+    }
+}
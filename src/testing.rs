@@ -0,0 +1,235 @@
+use crate::avl_tree::AVLTree;
+use crate::error::InvariantViolation;
+use crate::ordered_set::OrderedSet;
+use crate::rb_tree::RedBlackTree;
+use crate::storage::Backend;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::any::Any;
+use std::collections::BTreeSet;
+use std::fmt::Debug;
+use std::panic::{self, AssertUnwindSafe};
+
+/// One step of a differential test.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation<K> {
+    Insert(K),
+    Delete(K),
+}
+
+/// A tree that the differential tests can compare against a `BTreeSet`.
+pub trait TestSubject<K>: OrderedSet<K> + Default
+where
+    K: Ord + Clone,
+{
+    /// Names the tree in a [`Failure`].
+    const NAME: &'static str;
+
+    fn print_inorder(&self) -> Vec<K>;
+
+    fn search(&self, key: &K) -> bool;
+
+    fn validate(&self) -> Result<(), Vec<InvariantViolation<K>>>;
+}
+
+impl<K: Ord + Clone, B: Backend> TestSubject<K> for AVLTree<K, B> {
+    const NAME: &'static str = "AVL tree";
+
+    fn print_inorder(&self) -> Vec<K> {
+        self.tree.print_inorder()
+    }
+
+    fn search(&self, key: &K) -> bool {
+        self.tree.search(key).is_some()
+    }
+
+    fn validate(&self) -> Result<(), Vec<InvariantViolation<K>>> {
+        AVLTree::validate(self)
+    }
+}
+
+impl<K: Ord + Clone, B: Backend> TestSubject<K> for RedBlackTree<K, B> {
+    const NAME: &'static str = "red-black tree";
+
+    fn print_inorder(&self) -> Vec<K> {
+        self.tree.print_inorder()
+    }
+
+    fn search(&self, key: &K) -> bool {
+        self.tree.search(key).is_some()
+    }
+
+    fn validate(&self) -> Result<(), Vec<InvariantViolation<K>>> {
+        RedBlackTree::validate(self)
+    }
+}
+
+/// A sequence of operations after which a tree disagreed with the model or broke one of its
+/// invariants.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Failure<K> {
+    pub tree: &'static str,
+    /// The operations from an empty tree up to and including the one that failed.
+    pub operations: Vec<Operation<K>>,
+    pub reason: String,
+}
+
+/// Generates `count` operations on keys in `0..keys`, the same ones for the same `seed`.
+/// Inserts are somewhat more likely than deletes, so the trees grow over time.
+pub fn random_operations(seed: u64, count: usize, keys: i32) -> Vec<Operation<i32>> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    (0..count)
+        .map(|_| {
+            let key = rng.gen_range(0, keys);
+            if rng.gen_bool(0.6) {
+                Operation::Insert(key)
+            } else {
+                Operation::Delete(key)
+            }
+        })
+        .collect()
+}
+
+/// Applies `operations` to an empty `T` and to an empty `BTreeSet`. After every step, the
+/// keys returned by `print_inorder`, `is_empty`, `len`, `search` and the outcome of the step
+/// have to agree, and `validate` has to pass. Panics are caught and reported as failures.
+pub fn run<K, T>(operations: &[Operation<K>]) -> Result<(), Failure<K>>
+where
+    K: Ord + Clone + Debug,
+    T: TestSubject<K>,
+{
+    let mut done = 0;
+    let outcome = panic::catch_unwind(AssertUnwindSafe(|| {
+        let mut tree = T::default();
+        let mut model = BTreeSet::new();
+        for operation in operations {
+            step(&mut tree, &mut model, operation)?;
+            done += 1;
+        }
+        Ok(())
+    }));
+    let reason = match outcome {
+        Ok(Ok(())) => return Ok(()),
+        Ok(Err(reason)) => reason,
+        Err(payload) => format!("panicked: {}", panic_message(payload.as_ref())),
+    };
+    // A panic while dropping the tree is blamed on the whole sequence
+    let failed = (done + 1).min(operations.len());
+    Err(Failure {
+        tree: T::NAME,
+        operations: operations[..failed].to_vec(),
+        reason,
+    })
+}
+
+fn step<K, T>(tree: &mut T, model: &mut BTreeSet<K>, operation: &Operation<K>) -> Result<(), String>
+where
+    K: Ord + Clone + Debug,
+    T: TestSubject<K>,
+{
+    let key = match operation {
+        Operation::Insert(key) => {
+            let inserted = tree.insert(key.clone());
+            let expected = model.insert(key.clone());
+            if inserted != expected {
+                return Err(format!(
+                    "insert returned {}, expected {}",
+                    inserted, expected
+                ));
+            }
+            key
+        }
+        Operation::Delete(key) => {
+            let removed = tree.remove(key);
+            let expected = model.take(key);
+            if removed != expected {
+                return Err(format!(
+                    "remove returned {:?}, expected {:?}",
+                    removed, expected
+                ));
+            }
+            key
+        }
+    };
+
+    let keys = tree.print_inorder();
+    if !keys.iter().eq(model.iter()) {
+        return Err(format!(
+            "print_inorder returned {:?}, expected {:?}",
+            keys, model
+        ));
+    }
+    if tree.len() != model.len() {
+        return Err(format!("len is {}, expected {}", tree.len(), model.len()));
+    }
+    if tree.is_empty() != model.is_empty() {
+        return Err(format!(
+            "is_empty is {}, expected {}",
+            tree.is_empty(),
+            model.is_empty()
+        ));
+    }
+    if tree.search(key) != model.contains(key) {
+        return Err(format!("search for {:?} disagrees with the model", key));
+    }
+    if let Some(missing) = model.iter().find(|key| !tree.search(key)) {
+        return Err(format!("search for {:?} found nothing", missing));
+    }
+    tree.validate().map_err(|violations| {
+        let violations: Vec<String> = violations.iter().map(ToString::to_string).collect();
+        format!("invariants broken: {}", violations.join(", "))
+    })
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message
+    } else {
+        "unknown panic"
+    }
+}
+
+/// Leaves operations out of a failing sequence for as long as it keeps failing, first in large
+/// chunks and then one at a time, until leaving out any single operation makes it pass.
+pub fn shrink<K, T>(failure: Failure<K>) -> Failure<K>
+where
+    K: Ord + Clone + Debug,
+    T: TestSubject<K>,
+{
+    let mut failure = failure;
+    let mut chunk = (failure.operations.len() / 2).max(1);
+    loop {
+        let mut start = 0;
+        let mut shrunk = false;
+        while start < failure.operations.len() {
+            let end = (start + chunk).min(failure.operations.len());
+            let candidate = [&failure.operations[..start], &failure.operations[end..]].concat();
+            match run::<K, T>(&candidate) {
+                Err(smaller) => {
+                    failure = smaller;
+                    shrunk = true;
+                }
+                Ok(()) => start = end,
+            }
+        }
+        if chunk == 1 && !shrunk {
+            return failure;
+        }
+        chunk = (chunk / 2).max(1);
+    }
+}
+
+/// Runs `count` random operations on keys in `0..keys` against `T`, and shrinks the sequence
+/// to a minimal reproduction if it fails.
+pub fn check<T: TestSubject<i32>>(seed: u64, count: usize, keys: i32) -> Result<(), Failure<i32>> {
+    let operations = random_operations(seed, count, keys);
+    run::<i32, T>(&operations).map_err(shrink::<i32, T>)
+}
+
+/// Runs [`check`] on both trees with the same operations.
+pub fn check_both(seed: u64, count: usize, keys: i32) -> Result<(), Failure<i32>> {
+    check::<AVLTree<i32>>(seed, count, keys)?;
+    check::<RedBlackTree<i32>>(seed, count, keys)
+}
@@ -0,0 +1,152 @@
+mod common;
+
+use baltree::{AVLAugmentedMap, Augment, Backend};
+use common::{check_all, AugmentedMap, Check, Family};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::BTreeMap;
+use std::ops::Bound;
+
+struct Sum;
+
+impl Augment<i32, i64> for Sum {
+    type Summary = i64;
+
+    fn identity() -> i64 {
+        0
+    }
+
+    fn measure(_: &i32, value: &i64) -> i64 {
+        *value
+    }
+
+    fn combine(left: &i64, right: &i64) -> i64 {
+        left + right
+    }
+}
+
+struct Min;
+
+impl Augment<i32, i64> for Min {
+    type Summary = Option<i64>;
+
+    fn identity() -> Option<i64> {
+        None
+    }
+
+    fn measure(_: &i32, value: &i64) -> Option<i64> {
+        Some(*value)
+    }
+
+    fn combine(left: &Option<i64>, right: &Option<i64>) -> Option<i64> {
+        match (left, right) {
+            (Some(left), Some(right)) => Some(*left.min(right)),
+            _ => left.or(*right),
+        }
+    }
+}
+
+// Does not commute, so it catches summaries combined out of key order
+struct Keys;
+
+impl Augment<i32, i64> for Keys {
+    type Summary = Vec<i32>;
+
+    fn identity() -> Vec<i32> {
+        Vec::new()
+    }
+
+    fn measure(key: &i32, _: &i64) -> Vec<i32> {
+        vec![*key]
+    }
+
+    fn combine(left: &Vec<i32>, right: &Vec<i32>) -> Vec<i32> {
+        [left.as_slice(), right.as_slice()].concat()
+    }
+}
+
+fn bounds(rng: &mut StdRng) -> (Bound<i32>, Bound<i32>) {
+    let mut bound = || match rng.gen_range(0, 3) {
+        0 => Bound::Included(rng.gen_range(-10, 310)),
+        1 => Bound::Excluded(rng.gen_range(-10, 310)),
+        _ => Bound::Unbounded,
+    };
+    let (start, end) = (bound(), bound());
+    // BTreeMap::range panics on these, so they are left out
+    match (start, end) {
+        (Bound::Included(s) | Bound::Excluded(s), Bound::Included(e) | Bound::Excluded(e))
+            if s > e =>
+        {
+            (end, start)
+        }
+        (Bound::Excluded(s), Bound::Excluded(e)) if s == e => (Bound::Included(s), end),
+        _ => (start, end),
+    }
+}
+
+struct Folds;
+
+impl Check for Folds {
+    fn check<F: Family, B: Backend>(seed: u64) {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut sums = F::Augmented::<Sum, B>::default();
+        let mut mins = F::Augmented::<Min, B>::default();
+        let mut keys = F::Augmented::<Keys, B>::default();
+        let mut model = BTreeMap::new();
+        for step in 0..1_500 {
+            let key = rng.gen_range(0, 300);
+            if rng.gen_bool(0.7) {
+                let value = rng.gen_range(-1_000, 1_000);
+                let expected = model.insert(key, value);
+                assert_eq!(sums.insert(key, value), expected);
+                assert_eq!(mins.insert(key, value), expected);
+                assert_eq!(keys.insert(key, value), expected);
+            } else {
+                let expected = model.remove(&key);
+                assert_eq!(sums.remove(&key), expected);
+                assert_eq!(mins.remove(&key), expected);
+                assert_eq!(keys.remove(&key), expected);
+            }
+            if step % 10 != 0 {
+                continue;
+            }
+            assert_eq!(sums.fold(), model.values().sum::<i64>());
+            for _ in 0..10 {
+                let range = bounds(&mut rng);
+                let entries = || model.range(range);
+                assert_eq!(
+                    sums.fold_range(range),
+                    entries().map(|(_, v)| v).sum::<i64>()
+                );
+                assert_eq!(mins.fold_range(range), entries().map(|(_, v)| *v).min());
+                assert_eq!(
+                    keys.fold_range(range),
+                    entries().map(|(k, _)| *k).collect::<Vec<_>>()
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn folded_ranges_agree_with_btree_map() {
+    check_all::<Folds>(3);
+}
+
+#[test]
+fn folds_cover_range_syntax() {
+    let mut map = AVLAugmentedMap::<i32, i64, Sum>::new();
+    for key in 0..10 {
+        map.insert(key, key as i64);
+    }
+    assert_eq!(map.fold(), 45);
+    assert_eq!(map.fold_range(..), 45);
+    assert_eq!(map.fold_range(2..5), 9);
+    assert_eq!(map.fold_range(2..=5), 14);
+    assert_eq!(map.fold_range(..3), 3);
+    assert_eq!(map.fold_range(7..), 24);
+    assert_eq!(map.fold_range(20..30), 0);
+    // Replacing a value updates the summaries above it
+    assert_eq!(map.insert(3, 100), Some(3));
+    assert_eq!(map.fold_range(2..5), 106);
+}
@@ -0,0 +1,361 @@
+//! Runs the property checks of the test files on every kind of tree with every backend.
+#![allow(dead_code)]
+
+use baltree::iter::{MultiIntoIter, MultiIter};
+use baltree::persistent;
+use baltree::{
+    AVLAugmentedMap, AVLMap, AVLMultiset, AVLTree, ArenaBackend, Augment, Backend, Entry,
+    InvariantViolation, PersistentAVLTree, PersistentRedBlackTree, RcBackend, RedBlackAugmentedMap,
+    RedBlackMap, RedBlackMultiset, RedBlackTree, TreeError,
+};
+use std::ops::RangeBounds;
+
+/// The set methods the checks call, which both trees have under the same names.
+pub trait Set: Default {
+    fn from_sorted_iter(keys: impl IntoIterator<Item = i32>) -> Result<Self, TreeError>;
+    fn insert(&mut self, key: i32);
+    fn delete(&mut self, key: &i32);
+    fn len(&self) -> usize;
+    fn select(&self, index: usize) -> Option<i32>;
+    fn rank(&self, key: &i32) -> usize;
+    fn remove_at(&mut self, index: usize) -> Option<i32>;
+    fn floor(&self, key: &i32) -> Option<i32>;
+    fn ceiling(&self, key: &i32) -> Option<i32>;
+    fn predecessor(&self, key: &i32) -> Option<i32>;
+    fn successor(&self, key: &i32) -> Option<i32>;
+    fn height(&self) -> i32;
+    fn validate(&self) -> Result<(), Vec<InvariantViolation<i32>>>;
+    fn keys(&self) -> Vec<i32>;
+    fn split_off(&mut self, key: &i32) -> Self;
+    fn append(&mut self, other: &mut Self);
+    fn join(left: Self, pivot: i32, right: Self) -> Self;
+}
+
+macro_rules! set {
+    ($tree:ident) => {
+        impl<B: Backend> Set for $tree<i32, B> {
+            fn from_sorted_iter(keys: impl IntoIterator<Item = i32>) -> Result<Self, TreeError> {
+                $tree::from_sorted_iter(keys)
+            }
+
+            fn insert(&mut self, key: i32) {
+                $tree::insert(self, key);
+            }
+
+            fn delete(&mut self, key: &i32) {
+                $tree::delete(self, key);
+            }
+
+            fn len(&self) -> usize {
+                $tree::len(self)
+            }
+
+            fn select(&self, index: usize) -> Option<i32> {
+                $tree::select(self, index)
+            }
+
+            fn rank(&self, key: &i32) -> usize {
+                $tree::rank(self, key)
+            }
+
+            fn remove_at(&mut self, index: usize) -> Option<i32> {
+                $tree::remove_at(self, index)
+            }
+
+            fn floor(&self, key: &i32) -> Option<i32> {
+                $tree::floor(self, key)
+            }
+
+            fn ceiling(&self, key: &i32) -> Option<i32> {
+                $tree::ceiling(self, key)
+            }
+
+            fn predecessor(&self, key: &i32) -> Option<i32> {
+                $tree::predecessor(self, key)
+            }
+
+            fn successor(&self, key: &i32) -> Option<i32> {
+                $tree::successor(self, key)
+            }
+
+            fn height(&self) -> i32 {
+                $tree::height(self)
+            }
+
+            fn validate(&self) -> Result<(), Vec<InvariantViolation<i32>>> {
+                $tree::validate(self)
+            }
+
+            fn keys(&self) -> Vec<i32> {
+                self.iter().copied().collect()
+            }
+
+            fn split_off(&mut self, key: &i32) -> Self {
+                $tree::split_off(self, key)
+            }
+
+            fn append(&mut self, other: &mut Self) {
+                $tree::append(self, other)
+            }
+
+            fn join(left: Self, pivot: i32, right: Self) -> Self {
+                $tree::join(left, pivot, right)
+            }
+        }
+    };
+}
+
+set!(AVLTree);
+set!(RedBlackTree);
+
+/// The map methods the checks call, for maps from `i32` to `usize`.
+pub trait Map<B: Backend>: Default {
+    fn entry(&mut self, key: i32) -> Entry<'_, i32, usize, B>;
+    fn get(&self, key: &i32) -> Option<&usize>;
+    fn len(&self) -> usize;
+}
+
+macro_rules! map {
+    ($map:ident) => {
+        impl<B: Backend> Map<B> for $map<i32, usize, B> {
+            fn entry(&mut self, key: i32) -> Entry<'_, i32, usize, B> {
+                $map::entry(self, key)
+            }
+
+            fn get(&self, key: &i32) -> Option<&usize> {
+                $map::get(self, key)
+            }
+
+            fn len(&self) -> usize {
+                $map::len(self)
+            }
+        }
+    };
+}
+
+map!(AVLMap);
+map!(RedBlackMap);
+
+/// The multiset methods the checks call.
+pub trait Multiset<B: Backend>:
+    Default + IntoIterator<Item = i32, IntoIter = MultiIntoIter<i32, B>>
+{
+    fn insert(&mut self, key: i32);
+    fn remove_one(&mut self, key: &i32) -> bool;
+    fn remove_all(&mut self, key: &i32) -> usize;
+    fn count(&self, key: &i32) -> usize;
+    fn contains(&self, key: &i32) -> bool;
+    fn len(&self) -> usize;
+    fn is_empty(&self) -> bool;
+    fn iter(&self) -> MultiIter<'_, i32, B>;
+}
+
+macro_rules! multiset {
+    ($multiset:ident) => {
+        impl<B: Backend> Multiset<B> for $multiset<i32, B> {
+            fn insert(&mut self, key: i32) {
+                $multiset::insert(self, key);
+            }
+
+            fn remove_one(&mut self, key: &i32) -> bool {
+                $multiset::remove_one(self, key)
+            }
+
+            fn remove_all(&mut self, key: &i32) -> usize {
+                $multiset::remove_all(self, key)
+            }
+
+            fn count(&self, key: &i32) -> usize {
+                $multiset::count(self, key)
+            }
+
+            fn contains(&self, key: &i32) -> bool {
+                $multiset::contains(self, key)
+            }
+
+            fn len(&self) -> usize {
+                $multiset::len(self)
+            }
+
+            fn is_empty(&self) -> bool {
+                $multiset::is_empty(self)
+            }
+
+            fn iter(&self) -> MultiIter<'_, i32, B> {
+                $multiset::iter(self)
+            }
+        }
+    };
+}
+
+multiset!(AVLMultiset);
+multiset!(RedBlackMultiset);
+
+/// The methods of maps from `i32` to `i64` that keep summaries of the kind `A`.
+pub trait AugmentedMap<A: Augment<i32, i64>>: Default {
+    fn insert(&mut self, key: i32, value: i64) -> Option<i64>;
+    fn remove(&mut self, key: &i32) -> Option<i64>;
+    fn fold(&self) -> A::Summary;
+    fn fold_range(&self, range: impl RangeBounds<i32>) -> A::Summary;
+}
+
+macro_rules! augmented_map {
+    ($map:ident) => {
+        impl<A: Augment<i32, i64>, B: Backend> AugmentedMap<A> for $map<i32, i64, A, B> {
+            fn insert(&mut self, key: i32, value: i64) -> Option<i64> {
+                $map::insert(self, key, value)
+            }
+
+            fn remove(&mut self, key: &i32) -> Option<i64> {
+                $map::remove(self, key)
+            }
+
+            fn fold(&self) -> A::Summary {
+                $map::fold(self)
+            }
+
+            fn fold_range(&self, range: impl RangeBounds<i32>) -> A::Summary {
+                $map::fold_range(self, range)
+            }
+        }
+    };
+}
+
+augmented_map!(AVLAugmentedMap);
+augmented_map!(RedBlackAugmentedMap);
+
+/// The methods of persistent trees the checks call.
+pub trait Persistent: Clone + Default {
+    fn insert(&self, key: i32) -> Self;
+    fn remove(&self, key: &i32) -> Self;
+    fn len(&self) -> usize;
+    fn is_empty(&self) -> bool;
+    fn height(&self) -> i32;
+    fn contains(&self, key: &i32) -> bool;
+    fn first(&self) -> Option<&i32>;
+    fn last(&self) -> Option<&i32>;
+    fn iter(&self) -> persistent::Iter<'_, i32>;
+}
+
+macro_rules! persistent {
+    ($tree:ident) => {
+        impl Persistent for $tree<i32> {
+            fn insert(&self, key: i32) -> Self {
+                $tree::insert(self, key)
+            }
+
+            fn remove(&self, key: &i32) -> Self {
+                $tree::remove(self, key)
+            }
+
+            fn len(&self) -> usize {
+                $tree::len(self)
+            }
+
+            fn is_empty(&self) -> bool {
+                $tree::is_empty(self)
+            }
+
+            fn height(&self) -> i32 {
+                $tree::height(self)
+            }
+
+            fn contains(&self, key: &i32) -> bool {
+                $tree::contains(self, key)
+            }
+
+            fn first(&self) -> Option<&i32> {
+                $tree::first(self)
+            }
+
+            fn last(&self) -> Option<&i32> {
+                $tree::last(self)
+            }
+
+            fn iter(&self) -> persistent::Iter<'_, i32> {
+                $tree::iter(self)
+            }
+        }
+    };
+}
+
+persistent!(PersistentAVLTree);
+persistent!(PersistentRedBlackTree);
+
+/// Builds a set by inserting `keys` one at a time.
+pub fn build<S: Set>(keys: impl IntoIterator<Item = i32>) -> S {
+    let mut tree = S::default();
+    for key in keys {
+        tree.insert(key);
+    }
+    tree
+}
+
+/// A kind of balanced tree, naming its set, map, multiset and augmented map types for each
+/// backend, and its persistent tree.
+pub trait Family {
+    type Set<B: Backend>: Set;
+    type Map<B: Backend>: Map<B>;
+    type Multiset<B: Backend>: Multiset<B>;
+    type Augmented<A: Augment<i32, i64>, B: Backend>: AugmentedMap<A>;
+    type Persistent: Persistent;
+
+    /// The largest height a tree of this kind with `len` keys may have.
+    fn max_height(len: usize) -> f64;
+}
+
+pub struct Avl;
+
+pub struct RedBlack;
+
+impl Family for Avl {
+    type Set<B: Backend> = AVLTree<i32, B>;
+    type Map<B: Backend> = AVLMap<i32, usize, B>;
+    type Multiset<B: Backend> = AVLMultiset<i32, B>;
+    type Augmented<A: Augment<i32, i64>, B: Backend> = AVLAugmentedMap<i32, i64, A, B>;
+    type Persistent = PersistentAVLTree<i32>;
+
+    fn max_height(len: usize) -> f64 {
+        1.4405 * ((len + 2) as f64).log2() - 0.3277
+    }
+}
+
+impl Family for RedBlack {
+    type Set<B: Backend> = RedBlackTree<i32, B>;
+    type Map<B: Backend> = RedBlackMap<i32, usize, B>;
+    type Multiset<B: Backend> = RedBlackMultiset<i32, B>;
+    type Augmented<A: Augment<i32, i64>, B: Backend> = RedBlackAugmentedMap<i32, i64, A, B>;
+    type Persistent = PersistentRedBlackTree<i32>;
+
+    fn max_height(len: usize) -> f64 {
+        2.0 * ((len + 1) as f64).log2()
+    }
+}
+
+/// A property that should hold for every kind of tree on every backend.
+pub trait Check {
+    fn check<F: Family, B: Backend>(seed: u64);
+}
+
+/// Runs `C` with each seed below `seeds` on trees of kind `F`, both with Rc and arena storage.
+pub fn check_backends<C: Check, F: Family>(seeds: u64) {
+    for seed in 0..seeds {
+        C::check::<F, RcBackend>(seed);
+        C::check::<F, ArenaBackend>(seed);
+    }
+}
+
+/// Runs `C` with each seed below `seeds` on every kind of tree, for checks that do not use
+/// the backend.
+pub fn check_kinds<C: Check>(seeds: u64) {
+    for seed in 0..seeds {
+        C::check::<Avl, RcBackend>(seed);
+        C::check::<RedBlack, RcBackend>(seed);
+    }
+}
+
+/// Runs `C` with each seed below `seeds` on every kind of tree with every backend.
+pub fn check_all<C: Check>(seeds: u64) {
+    check_backends::<C, Avl>(seeds);
+    check_backends::<C, RedBlack>(seeds);
+}
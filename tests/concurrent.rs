@@ -0,0 +1,135 @@
+use baltree::{
+    AVLMap, AVLTree, ArenaBackend, ConcurrentTree, IntervalTree, RedBlackMap, RedBlackTree,
+};
+use std::sync::Arc;
+use std::thread;
+
+fn assert_send_sync<T: Send + Sync>() {}
+
+#[test]
+fn arena_trees_are_send_and_sync() {
+    assert_send_sync::<AVLTree<i32, ArenaBackend>>();
+    assert_send_sync::<RedBlackTree<i32, ArenaBackend>>();
+    assert_send_sync::<AVLMap<i32, String, ArenaBackend>>();
+    assert_send_sync::<RedBlackMap<i32, String, ArenaBackend>>();
+    assert_send_sync::<IntervalTree<i32, ArenaBackend>>();
+    assert_send_sync::<ConcurrentTree<AVLTree<i32, ArenaBackend>>>();
+}
+
+#[test]
+fn trees_move_between_threads() {
+    let mut tree = RedBlackTree::<i32, ArenaBackend>::default();
+    for key in 0..1000 {
+        tree.insert(key);
+    }
+    let tree = thread::spawn(move || {
+        for key in 1000..2000 {
+            tree.insert(key);
+        }
+        tree
+    })
+    .join()
+    .unwrap();
+    assert_eq!(
+        tree.iter().copied().collect::<Vec<_>>(),
+        (0..2000).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn readers_share_a_tree() {
+    let mut tree = AVLTree::<i32, ArenaBackend>::default();
+    for key in 0..10_000 {
+        tree.insert(key);
+    }
+    let tree = &tree;
+    thread::scope(|scope| {
+        for reader in 0..8 {
+            scope.spawn(move || {
+                for key in (reader..10_000).step_by(8) {
+                    assert!(tree.contains(&key));
+                    assert_eq!(
+                        tree.range(key..key + 3).count(),
+                        (10_000 - key).min(3) as usize
+                    );
+                }
+            });
+        }
+    });
+}
+
+// Each writer owns the keys congruent to its index, so the final contents are known however
+// the threads interleave, while readers check that every range they see is sorted and in bounds
+fn stress<T>(tree: ConcurrentTree<T>) -> T
+where
+    T: baltree::OrderedSet<i32> + Send + Sync + 'static,
+{
+    const WRITERS: i32 = 4;
+    const KEYS: i32 = 4_000;
+    let tree = Arc::new(tree);
+    let mut handles = Vec::new();
+    for writer in 0..WRITERS {
+        let tree = Arc::clone(&tree);
+        handles.push(thread::spawn(move || {
+            for key in (writer..KEYS).step_by(WRITERS as usize) {
+                assert!(tree.insert(key));
+                assert!(!tree.insert(key));
+            }
+            for key in (writer..KEYS).step_by(2 * WRITERS as usize) {
+                assert_eq!(tree.remove(&key), Some(key));
+            }
+        }));
+    }
+    for reader in 0..4 {
+        let tree = Arc::clone(&tree);
+        handles.push(thread::spawn(move || {
+            for round in 0..2_000 {
+                let start = (round * 7 + reader * 13) % KEYS;
+                let keys = tree.range(start..start + 50);
+                assert!(keys.windows(2).all(|pair| pair[0] < pair[1]));
+                assert!(keys.iter().all(|key| (start..start + 50).contains(key)));
+                tree.contains(&start);
+            }
+        }));
+    }
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    Arc::try_unwrap(tree).ok().unwrap().into_inner()
+}
+
+#[test]
+fn concurrent_avl_inserts_and_removes() {
+    let tree = stress(ConcurrentTree::<AVLTree<i32, ArenaBackend>>::default());
+    let expected: Vec<i32> = (0..4_000).filter(|key| key % 8 >= 4).collect();
+    assert_eq!(tree.iter().copied().collect::<Vec<_>>(), expected);
+    assert_eq!(tree.len(), expected.len());
+}
+
+#[test]
+fn concurrent_red_black_inserts_and_removes() {
+    let tree = stress(ConcurrentTree::<RedBlackTree<i32, ArenaBackend>>::default());
+    let expected: Vec<i32> = (0..4_000).filter(|key| key % 8 >= 4).collect();
+    assert_eq!(tree.iter().copied().collect::<Vec<_>>(), expected);
+    assert_eq!(tree.validate(), Ok(()));
+}
+
+#[test]
+fn writes_under_one_lock_are_seen_together() {
+    let tree = Arc::new(ConcurrentTree::<AVLTree<i32, ArenaBackend>>::default());
+    let writer = {
+        let tree = Arc::clone(&tree);
+        thread::spawn(move || {
+            for batch in 0..500 {
+                let mut guard = tree.write();
+                guard.insert(2 * batch);
+                guard.insert(2 * batch + 1);
+            }
+        })
+    };
+    while !writer.is_finished() {
+        assert_eq!(tree.len() % 2, 0);
+    }
+    writer.join().unwrap();
+    assert_eq!(tree.len(), 1000);
+}
@@ -0,0 +1,231 @@
+use baltree::{AVLMap, AVLTree, ArenaBackend, Backend, RcBackend, RedBlackMap, RedBlackTree};
+use std::ops::Bound;
+
+#[test]
+fn cursors_walk_both_ways_and_wrap_around() {
+    let tree = AVLTree::<i32>::from_iter([10, 20, 30]);
+    let mut cursor = tree.cursor_front();
+    assert_eq!(cursor.key(), Some(&10));
+    assert_eq!(cursor.peek_prev(), None);
+    assert_eq!(cursor.peek_next(), Some(&20));
+    cursor.move_next();
+    cursor.move_next();
+    assert_eq!(cursor.key(), Some(&30));
+    cursor.move_next();
+    assert_eq!(cursor.key(), None);
+    assert_eq!(cursor.peek_next(), Some(&10));
+    assert_eq!(cursor.peek_prev(), Some(&30));
+    cursor.move_next();
+    assert_eq!(cursor.key(), Some(&10));
+    cursor.move_prev();
+    assert_eq!(cursor.key(), None);
+
+    let mut keys = Vec::new();
+    let mut cursor = tree.cursor_back();
+    while let Some(key) = cursor.key() {
+        keys.push(*key);
+        cursor.move_prev();
+    }
+    assert_eq!(keys, [30, 20, 10]);
+
+    let empty = RedBlackTree::<i32>::new();
+    let mut cursor = empty.cursor_front();
+    assert_eq!(cursor.key(), None);
+    cursor.move_next();
+    assert_eq!(cursor.peek_prev(), None);
+}
+
+#[test]
+fn cursors_seek_lower_bounds() {
+    let tree = RedBlackTree::<i32>::from_iter((0..100).map(|key| key * 2));
+    assert_eq!(tree.lower_bound(Bound::Included(&10)).key(), Some(&10));
+    assert_eq!(tree.lower_bound(Bound::Excluded(&10)).key(), Some(&12));
+    assert_eq!(tree.lower_bound(Bound::Included(&11)).key(), Some(&12));
+    assert_eq!(tree.lower_bound(Bound::Unbounded).key(), Some(&0));
+    assert_eq!(tree.lower_bound(Bound::Included(&199)).key(), None);
+
+    let mut cursor = tree.cursor_back();
+    cursor.seek_lower_bound(Bound::Included(&51));
+    assert_eq!(cursor.key(), Some(&52));
+    assert_eq!(cursor.peek_prev(), Some(&50));
+}
+
+// Intersects two trees by leapfrogging: each cursor seeks to the other's key from the root, but
+// steps to neighbouring keys through the parent links
+#[test]
+fn cursors_drive_merge_style_scans() {
+    let left = AVLTree::<i32>::from_iter((0..1_000).filter(|key| key % 3 == 0));
+    let right = RedBlackTree::<i32>::from_iter((0..1_000).filter(|key| key % 5 == 0));
+    let (mut a, mut b) = (left.cursor_front(), right.cursor_front());
+    let mut common = Vec::new();
+    while let (Some(x), Some(y)) = (a.key(), b.key()) {
+        if x < y {
+            a.seek_lower_bound(Bound::Included(y));
+        } else if y < x {
+            b.seek_lower_bound(Bound::Included(x));
+        } else {
+            common.push(*x);
+            a.move_next();
+            b.move_next();
+        }
+    }
+    assert_eq!(common, (0..1_000).step_by(15).collect::<Vec<_>>());
+}
+
+fn edit_avl<B: Backend>() {
+    let mut tree = AVLTree::<i32, B>::default();
+    let mut cursor = tree.cursor_front_mut();
+    for key in (0..200).map(|key| key * 10) {
+        cursor.insert_before(key, ());
+    }
+    assert_eq!(cursor.key(), None);
+    cursor.move_next();
+    // Fill in a key after every existing one, stepping over the new key each time
+    while cursor.key().is_some() {
+        let key = cursor.key().unwrap() + 5;
+        cursor.insert_after(key, ());
+        cursor.move_next();
+        assert_eq!(cursor.key(), Some(&key));
+        cursor.move_next();
+    }
+    assert_eq!(tree.validate(), Ok(()));
+    assert_eq!(
+        tree.iter().copied().collect::<Vec<_>>(),
+        (0..400).map(|key| key * 5).collect::<Vec<_>>()
+    );
+
+    // Remove every key ending in 5, from the back
+    let mut cursor = tree.lower_bound_mut(Bound::Included(&1_000));
+    while let Some(&key) = cursor.key() {
+        if key % 10 == 5 {
+            assert_eq!(cursor.remove_current(), Some((key, ())));
+        } else {
+            cursor.move_next();
+        }
+    }
+    assert_eq!(tree.validate(), Ok(()));
+    assert_eq!(tree.len(), 300);
+    assert!(tree.iter().all(|key| *key < 1_000 || key % 10 == 0));
+}
+
+fn edit_red_black<B: Backend>() {
+    let mut tree = RedBlackTree::<i32, B>::default();
+    let mut cursor = tree.cursor_back_mut();
+    for key in (0..200).rev() {
+        cursor.insert_after(key * 10, ());
+    }
+    // Fill in a key before every existing one but the smallest, stepping over the new key
+    cursor.move_prev();
+    while let Some(&key) = cursor.key() {
+        if key > 0 {
+            cursor.insert_before(key - 5, ());
+            cursor.move_prev();
+        }
+        cursor.move_prev();
+    }
+    assert_eq!(tree.validate(), Ok(()));
+    assert_eq!(tree.len(), 399);
+
+    // Remove the original keys, stepping over the filled in ones
+    let mut cursor = tree.cursor_front_mut();
+    for key in 0..200 {
+        assert_eq!(cursor.remove_current(), Some((key * 10, ())));
+        cursor.move_next();
+    }
+    assert_eq!(tree.validate(), Ok(()));
+    assert_eq!(
+        tree.iter().copied().collect::<Vec<_>>(),
+        (0..199).map(|key| key * 10 + 5).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn cursors_insert_and_remove_with_rebalancing() {
+    edit_avl::<RcBackend>();
+    edit_avl::<ArenaBackend>();
+    edit_red_black::<RcBackend>();
+    edit_red_black::<ArenaBackend>();
+}
+
+#[test]
+fn removing_every_key_through_a_cursor_empties_the_tree() {
+    let mut tree = RedBlackTree::<i32>::from_iter(0..500);
+    let mut cursor = tree.cursor_front_mut();
+    for key in 0..500 {
+        assert_eq!(cursor.remove_current(), Some((key, ())));
+    }
+    assert_eq!(cursor.remove_current(), None);
+    assert!(tree.is_empty());
+    assert_eq!(tree.validate(), Ok(()));
+}
+
+#[test]
+fn map_cursors_reach_the_values() {
+    let mut map = AVLMap::<i32, String>::new();
+    let mut other = RedBlackMap::<i32, String>::new();
+    for key in 0..10 {
+        map.insert(key * 2, key.to_string());
+        other.insert(key, key.to_string());
+    }
+    let mut cursor = map.lower_bound_mut(Bound::Excluded(&4));
+    cursor.value_mut().unwrap().push('!');
+    cursor.insert_after(7, "inserted".to_string());
+    assert_eq!(cursor.as_cursor().peek_next(), Some(&7));
+    assert_eq!(map.get(&6).map(String::as_str), Some("3!"));
+    assert_eq!(map.get(&7).map(String::as_str), Some("inserted"));
+    assert_eq!(map.len(), 11);
+
+    let mut cursor = other.cursor_back_mut();
+    assert_eq!(cursor.value().map(String::as_str), Some("9"));
+    assert_eq!(cursor.remove_current(), Some((9, "9".to_string())));
+    assert_eq!(cursor.key(), None);
+    assert_eq!(other.len(), 9);
+    assert_eq!(other.cursor_front().value().map(String::as_str), Some("0"));
+}
+
+#[test]
+#[should_panic(expected = "must sort between its neighbours")]
+fn inserting_out_of_order_panics() {
+    let mut tree = AVLTree::<i32>::from_iter([1, 5, 9]);
+    let mut cursor = tree.lower_bound_mut(Bound::Included(&5));
+    cursor.insert_after(3, ());
+}
+
+// Fills the gaps between keys in a random order, so the new nodes land both in free child slots
+// of the cursor's node and of its neighbours, all over the tree
+#[test]
+fn inserting_at_random_positions_keeps_trees_valid() {
+    let mut avl = AVLTree::<i32>::from_iter((0..300).map(|key| key * 4));
+    let mut rb = RedBlackTree::<i32>::from_iter((0..300).map(|key| key * 4));
+    for step in 0..600 {
+        let at = (step * 7919) % 300 * 4;
+        let key = if step < 300 { at + 1 } else { at - 1 };
+        if step < 300 {
+            avl.lower_bound_mut(Bound::Included(&at))
+                .insert_after(key, ());
+            rb.lower_bound_mut(Bound::Included(&at))
+                .insert_after(key, ());
+        } else if at > 0 {
+            avl.lower_bound_mut(Bound::Included(&at))
+                .insert_before(key, ());
+            rb.lower_bound_mut(Bound::Included(&at))
+                .insert_before(key, ());
+        }
+        assert_eq!(avl.validate(), Ok(()));
+        assert_eq!(rb.validate(), Ok(()));
+    }
+    let expected: Vec<i32> = (0..300)
+        .flat_map(|key| [key * 4 - 1, key * 4, key * 4 + 1])
+        .filter(|key| *key >= 0)
+        .collect();
+    assert!(avl.iter().copied().eq(expected.iter().copied()));
+    assert!(rb.iter().copied().eq(expected.iter().copied()));
+
+    let mut empty = RedBlackTree::<i32>::new();
+    let mut cursor = empty.cursor_front_mut();
+    cursor.insert_after(2, ());
+    cursor.insert_after(0, ());
+    cursor.insert_before(3, ());
+    assert_eq!(empty.validate(), Ok(()));
+    assert!(empty.iter().copied().eq([0, 2, 3]));
+}
@@ -0,0 +1,142 @@
+use baltree::testing::{self, Operation, TestSubject};
+use baltree::{AVLTree, ArenaBackend, InvariantViolation, OrderedSet, RedBlackTree};
+use std::ops::Bound;
+
+#[test]
+fn avl_trees_agree_with_btreeset() {
+    for seed in 0..40 {
+        assert_eq!(testing::check::<AVLTree<i32>>(seed, 500, 50), Ok(()));
+        assert_eq!(
+            testing::check::<AVLTree<i32, ArenaBackend>>(seed, 500, 50),
+            Ok(())
+        );
+    }
+}
+
+#[test]
+fn red_black_trees_agree_with_btreeset() {
+    for seed in 0..40 {
+        assert_eq!(testing::check::<RedBlackTree<i32>>(seed, 500, 50), Ok(()));
+        assert_eq!(
+            testing::check::<RedBlackTree<i32, ArenaBackend>>(seed, 500, 50),
+            Ok(())
+        );
+    }
+    assert_eq!(testing::check_both(40, 2_000, 300), Ok(()));
+}
+
+// An AVL tree that reports removing 7 but keeps it, as a known failure to shrink
+#[derive(Default)]
+struct Forgetful(AVLTree<i32>);
+
+impl OrderedSet<i32> for Forgetful {
+    fn insert(&mut self, key: i32) -> bool {
+        self.0.insert(key)
+    }
+
+    fn remove(&mut self, key: &i32) -> Option<i32> {
+        if *key == 7 {
+            OrderedSet::contains(&self.0, key).then_some(7)
+        } else {
+            self.0.delete(key)
+        }
+    }
+
+    fn contains(&self, key: &i32) -> bool {
+        OrderedSet::contains(&self.0, key)
+    }
+
+    fn len(&self) -> usize {
+        OrderedSet::len(&self.0)
+    }
+
+    fn height(&self) -> i32 {
+        self.0.height()
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = &i32> + '_> {
+        OrderedSet::iter(&self.0)
+    }
+
+    fn range(&self, start: Bound<&i32>, end: Bound<&i32>) -> Box<dyn Iterator<Item = &i32> + '_> {
+        OrderedSet::range(&self.0, start, end)
+    }
+
+    fn min(&self) -> Option<i32> {
+        OrderedSet::min(&self.0)
+    }
+
+    fn max(&self) -> Option<i32> {
+        OrderedSet::max(&self.0)
+    }
+
+    fn inorder(&self) -> Vec<i32> {
+        self.0.inorder()
+    }
+
+    fn preorder(&self) -> Vec<i32> {
+        self.0.preorder()
+    }
+
+    fn postorder(&self) -> Vec<i32> {
+        self.0.postorder()
+    }
+
+    fn count_leaves(&self) -> i32 {
+        self.0.count_leaves()
+    }
+
+    fn print_structure(&self) {
+        self.0.print_structure()
+    }
+}
+
+impl TestSubject<i32> for Forgetful {
+    const NAME: &'static str = "forgetful tree";
+
+    fn print_inorder(&self) -> Vec<i32> {
+        self.0.iter().copied().collect()
+    }
+
+    fn search(&self, key: &i32) -> bool {
+        OrderedSet::contains(&self.0, key)
+    }
+
+    fn validate(&self) -> Result<(), Vec<InvariantViolation<i32>>> {
+        self.0.validate()
+    }
+}
+
+#[test]
+fn failures_shrink_to_a_minimal_sequence() {
+    let failure = (0..)
+        .find_map(|seed| testing::check::<Forgetful>(seed, 300, 20).err())
+        .unwrap();
+    assert_eq!(failure.tree, "forgetful tree");
+    assert_eq!(
+        failure.operations,
+        vec![Operation::Insert(7), Operation::Delete(7)]
+    );
+    assert_eq!(failure.reason, "print_inorder returned [7], expected {}");
+    assert_eq!(
+        testing::run::<_, Forgetful>(&failure.operations),
+        Err(failure)
+    );
+}
+
+#[test]
+fn operations_depend_only_on_the_seed() {
+    assert_eq!(
+        testing::random_operations(3, 100, 20),
+        testing::random_operations(3, 100, 20)
+    );
+    assert_ne!(
+        testing::random_operations(3, 100, 20),
+        testing::random_operations(4, 100, 20)
+    );
+    assert!(testing::random_operations(5, 1_000, 20)
+        .iter()
+        .all(|operation| match operation {
+            Operation::Insert(key) | Operation::Delete(key) => (0..20).contains(key),
+        }));
+}
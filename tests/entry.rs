@@ -0,0 +1,86 @@
+mod common;
+
+use baltree::{AVLTree, Backend, Entry, RedBlackMap, RedBlackTree};
+use common::{check_all, Check, Family, Map, Set};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::BTreeMap;
+
+// Counts words through the entry API of each map, and the same words in a `BTreeMap`
+struct Counting;
+
+impl Check for Counting {
+    fn check<F: Family, B: Backend>(seed: u64) {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut map = F::Map::<B>::default();
+        let mut model = BTreeMap::new();
+        for _ in 0..3_000 {
+            let key = rng.gen_range(0, 400);
+            match rng.gen_range(0, 3) {
+                0 => *map.entry(key).or_insert(0) += 1,
+                1 => *map.entry(key).or_default() += 1,
+                _ => {
+                    map.entry(key).and_modify(|count| *count += 1).or_insert(1);
+                }
+            }
+            *model.entry(key).or_insert(0) += 1;
+        }
+        assert_eq!(map.len(), model.len());
+        for (key, count) in &model {
+            assert_eq!(map.get(key), Some(count));
+        }
+    }
+}
+
+#[test]
+fn entries_count_like_btree_map() {
+    check_all::<Counting>(3);
+}
+
+#[test]
+fn vacant_and_occupied_entries() {
+    let mut map = RedBlackMap::<i32, String>::new();
+    match map.entry(1) {
+        Entry::Vacant(entry) => {
+            assert_eq!(entry.key(), &1);
+            entry.insert("one".to_string()).push('!');
+        }
+        Entry::Occupied(_) => panic!("the map is empty"),
+    }
+    match map.entry(1) {
+        Entry::Occupied(mut entry) => {
+            assert_eq!(entry.key(), &1);
+            assert_eq!(entry.get(), "one!");
+            assert_eq!(entry.insert("uno".to_string()), "one!");
+        }
+        Entry::Vacant(_) => panic!("the key was inserted"),
+    }
+    assert_eq!(map.get(&1).map(String::as_str), Some("uno"));
+
+    // A vacant entry that is dropped inserts nothing
+    let key = match map.entry(2) {
+        Entry::Vacant(entry) => entry.into_key(),
+        Entry::Occupied(_) => panic!("the key is missing"),
+    };
+    assert_eq!(key, 2);
+    assert_eq!(map.len(), 1);
+    assert_eq!(map.entry(3).or_insert_with(|| "three".to_string()), "three");
+    assert_eq!(map.entry(3).or_insert_with(|| unreachable!()), "three");
+    assert_eq!(map.entry(4).and_modify(|_| unreachable!()).key(), &4);
+}
+
+#[test]
+fn set_entries_insert_keys_with_one_search() {
+    let mut avl = AVLTree::<i32>::new();
+    let mut rb = RedBlackTree::<i32>::new();
+    for key in (0..500).rev() {
+        avl.entry(key).or_insert(());
+        rb.entry(key % 250).or_insert(());
+    }
+    assert_eq!(avl.validate(), Ok(()));
+    assert_eq!(rb.validate(), Ok(()));
+    assert!(avl.keys().into_iter().eq(0..500));
+    assert!(rb.keys().into_iter().eq(0..250));
+    assert!(matches!(avl.entry(7), Entry::Occupied(_)));
+    assert!(matches!(rb.entry(250), Entry::Vacant(_)));
+}
@@ -0,0 +1,121 @@
+use baltree::{ArenaBackend, Backend, IntervalTree, RcBackend, TreeError};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use std::collections::BTreeSet;
+use std::ops::Range;
+
+// The intervals of `model` that overlap `query`, found by looking at every one
+fn brute_force(model: &BTreeSet<(i32, i32)>, query: &Range<i32>) -> Vec<(i32, i32)> {
+    model
+        .iter()
+        .filter(|(start, end)| *start < query.end && query.start < *end)
+        .copied()
+        .collect()
+}
+
+fn assert_queries<B: Backend>(
+    tree: &IntervalTree<i32, B>,
+    model: &BTreeSet<(i32, i32)>,
+    rng: &mut StdRng,
+) {
+    for _ in 0..50 {
+        let start = rng.gen_range(-10, 1_010);
+        let query = start..start + rng.gen_range(1, 60);
+        let expected = brute_force(model, &query);
+        assert_eq!(
+            tree.overlapping(query.clone()).cloned().collect::<Vec<_>>(),
+            expected
+        );
+        assert_eq!(tree.any_overlap(query.clone()), !expected.is_empty());
+
+        let point = query.start;
+        assert_eq!(
+            tree.stab(point).cloned().collect::<Vec<_>>(),
+            brute_force(model, &(point..point + 1))
+        );
+    }
+}
+
+fn random_intervals(rng: &mut StdRng, count: usize) -> Vec<(i32, i32)> {
+    (0..count)
+        .map(|_| {
+            let start = rng.gen_range(0, 1_000);
+            // Mostly short intervals with a few long ones, so the largest ends vary a lot
+            let length = if rng.gen_bool(0.1) {
+                rng.gen_range(100, 500)
+            } else {
+                rng.gen_range(1, 20)
+            };
+            (start, start + length)
+        })
+        .collect()
+}
+
+fn queries_agree<B: Backend>(seed: u64) {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut tree = IntervalTree::<i32, B>::default();
+    let mut model = BTreeSet::new();
+    let mut intervals = random_intervals(&mut rng, 400);
+    for interval in intervals.iter().copied() {
+        assert_eq!(tree.insert(interval), Ok(model.insert(interval)));
+    }
+    assert_eq!(tree.len(), model.len());
+    assert_queries(&tree, &model, &mut rng);
+
+    // Removing in another order runs the delete fixups and their rotations all over the tree,
+    // and the largest ends have to follow every one of them
+    intervals.shuffle(&mut rng);
+    for (index, interval) in intervals.iter().enumerate() {
+        assert_eq!(
+            tree.remove(interval),
+            model.take(interval),
+            "removing {:?}",
+            interval
+        );
+        if index % 20 == 0 {
+            assert_queries(&tree, &model, &mut rng);
+        }
+    }
+    assert!(tree.is_empty());
+}
+
+#[test]
+fn queries_agree_with_a_brute_force_filter() {
+    for seed in 0..5 {
+        queries_agree::<RcBackend>(seed);
+        queries_agree::<ArenaBackend>(seed);
+    }
+}
+
+#[test]
+fn empty_queries_and_intervals() {
+    let mut tree = IntervalTree::<i32>::new();
+    assert_eq!(tree.insert((3, 3)), Err(TreeError::EmptyInterval));
+    assert_eq!(tree.insert((5, 2)), Err(TreeError::EmptyInterval));
+    assert!(tree.is_empty());
+
+    assert_eq!(tree.insert((0, 10)), Ok(true));
+    assert_eq!(tree.insert((0, 10)), Ok(false));
+    assert_eq!(tree.overlapping(5..5).count(), 0);
+    #[allow(clippy::reversed_empty_ranges)]
+    let reversed = 8..2;
+    assert_eq!(tree.overlapping(reversed.clone()).count(), 0);
+    assert!(!tree.any_overlap(5..5));
+    assert!(!tree.any_overlap(reversed));
+}
+
+#[test]
+fn intervals_are_half_open() {
+    let mut tree = IntervalTree::<i32>::new();
+    for interval in [(0, 5), (5, 10), (10, 15)] {
+        tree.insert(interval).unwrap();
+    }
+    assert_eq!(tree.stab(5).collect::<Vec<_>>(), [&(5, 10)]);
+    assert_eq!(tree.overlapping(0..5).collect::<Vec<_>>(), [&(0, 5)]);
+    assert!(!tree.any_overlap(15..20));
+    assert_eq!(
+        tree.overlapping(4..11).collect::<Vec<_>>(),
+        [&(0, 5), &(5, 10), &(10, 15)]
+    );
+}
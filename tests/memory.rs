@@ -0,0 +1,112 @@
+use baltree::{AVLMap, AVLTree, RedBlackMap, RedBlackTree};
+use std::cell::Cell;
+use std::cmp::Ordering;
+use std::rc::Rc;
+
+// Key that records every drop in a shared counter
+struct Counted {
+    key: i32,
+    drops: Rc<Cell<usize>>,
+}
+
+impl Counted {
+    fn new(key: i32, drops: &Rc<Cell<usize>>) -> Self {
+        Counted {
+            key,
+            drops: drops.clone(),
+        }
+    }
+}
+
+impl Drop for Counted {
+    fn drop(&mut self) {
+        self.drops.set(self.drops.get() + 1);
+    }
+}
+
+impl PartialEq for Counted {
+    fn eq(&self, other: &Self) -> bool {
+        self.key == other.key
+    }
+}
+
+impl Eq for Counted {}
+
+impl PartialOrd for Counted {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Counted {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key.cmp(&other.key)
+    }
+}
+
+const KEYS: i32 = 1000;
+
+fn shuffled_keys() -> impl Iterator<Item = i32> {
+    (0..KEYS).map(|i| (i * 7919) % KEYS)
+}
+
+#[test]
+fn red_black_tree_frees_every_node_when_dropped() {
+    let drops = Rc::new(Cell::new(0));
+    {
+        let mut tree = RedBlackTree::new();
+        for key in shuffled_keys() {
+            tree.insert(Counted::new(key, &drops));
+        }
+        assert_eq!(drops.get(), 0);
+    }
+    assert_eq!(drops.get(), KEYS as usize);
+}
+
+#[test]
+fn avl_tree_frees_every_node_when_dropped() {
+    let drops = Rc::new(Cell::new(0));
+    {
+        let mut tree = AVLTree::new();
+        for key in shuffled_keys() {
+            tree.insert(Counted::new(key, &drops));
+        }
+        for key in 0..KEYS / 2 {
+            // The probe key is dropped too, alongside the removed node
+            tree.delete(&Counted::new(key * 2, &drops));
+        }
+        assert_eq!(drops.get(), KEYS as usize);
+    }
+    assert_eq!(drops.get(), KEYS as usize + KEYS as usize / 2);
+}
+
+#[test]
+fn maps_free_every_value_when_dropped() {
+    let drops = Rc::new(Cell::new(0));
+    {
+        let mut avl = AVLMap::new();
+        let mut rb = RedBlackMap::new();
+        for key in shuffled_keys() {
+            avl.insert(key, Counted::new(key, &drops));
+            rb.insert(key, Counted::new(key, &drops));
+        }
+    }
+    assert_eq!(drops.get(), 2 * KEYS as usize);
+}
+
+#[test]
+fn partly_consumed_into_iter_frees_the_rest() {
+    let drops = Rc::new(Cell::new(0));
+    let mut tree = RedBlackTree::new();
+    for key in shuffled_keys() {
+        tree.insert(Counted::new(key, &drops));
+    }
+    let mut iter = tree.into_iter();
+    for _ in 0..10 {
+        assert!(iter.next().is_some());
+        assert!(iter.next_back().is_some());
+    }
+    assert_eq!(drops.get(), 20);
+    drop(iter);
+    assert_eq!(drops.get(), KEYS as usize);
+}
@@ -0,0 +1,111 @@
+mod common;
+
+use baltree::{AVLMultiset, Backend, RedBlackMultiset};
+use common::{check_all, Check, Family, Multiset};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::{BTreeMap, VecDeque};
+
+// Every copy of every key in ascending order
+fn expand(model: &BTreeMap<i32, usize>) -> VecDeque<i32> {
+    model
+        .iter()
+        .flat_map(|(key, count)| std::iter::repeat_n(*key, *count))
+        .collect()
+}
+
+struct Counting;
+
+impl Check for Counting {
+    fn check<F: Family, B: Backend>(seed: u64) {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut multiset = F::Multiset::<B>::default();
+        let mut model = BTreeMap::<i32, usize>::new();
+        for step in 0..3_000 {
+            let key = rng.gen_range(0, 100);
+            match rng.gen_range(0, 10) {
+                0..=5 => {
+                    multiset.insert(key);
+                    *model.entry(key).or_insert(0) += 1;
+                }
+                6..=8 => {
+                    let present = model.contains_key(&key);
+                    assert_eq!(multiset.remove_one(&key), present);
+                    if present {
+                        *model.get_mut(&key).unwrap() -= 1;
+                        if model[&key] == 0 {
+                            model.remove(&key);
+                        }
+                    }
+                }
+                _ => {
+                    let expected = model.remove(&key).unwrap_or(0);
+                    assert_eq!(multiset.remove_all(&key), expected);
+                }
+            }
+            assert_eq!(multiset.count(&key), model.get(&key).copied().unwrap_or(0));
+            assert_eq!(multiset.contains(&key), model.contains_key(&key));
+            if step % 100 != 0 {
+                continue;
+            }
+            assert_eq!(multiset.len(), model.values().sum::<usize>());
+            assert_eq!(multiset.is_empty(), model.is_empty());
+
+            // Takes copies from both ends at random until the iterators meet
+            let mut expected = expand(&model);
+            let mut iter = multiset.iter();
+            assert_eq!(iter.len(), expected.len());
+            loop {
+                let (next, wanted) = if rng.gen_bool(0.5) {
+                    (iter.next(), expected.pop_front())
+                } else {
+                    (iter.next_back(), expected.pop_back())
+                };
+                assert_eq!(next.copied(), wanted);
+                assert_eq!(iter.len(), expected.len());
+                if wanted.is_none() {
+                    break;
+                }
+            }
+        }
+        let expected = expand(&model);
+        assert!(multiset.into_iter().rev().eq(expected.into_iter().rev()));
+    }
+}
+
+#[test]
+fn multisets_agree_with_counting_maps() {
+    check_all::<Counting>(3);
+}
+
+#[test]
+fn copies_are_counted_and_removed() {
+    let mut multiset = RedBlackMultiset::<&str>::new();
+    for word in ["b", "a", "b", "c", "b"] {
+        multiset.insert(word);
+    }
+    assert_eq!(multiset.len(), 5);
+    assert_eq!(multiset.count(&"b"), 3);
+    assert_eq!(
+        multiset.iter().copied().collect::<Vec<_>>(),
+        ["a", "b", "b", "b", "c"]
+    );
+    assert_eq!(
+        multiset.iter().rev().copied().collect::<Vec<_>>(),
+        ["c", "b", "b", "b", "a"]
+    );
+    assert!(multiset.remove_one(&"b"));
+    assert_eq!(multiset.count(&"b"), 2);
+    assert_eq!(multiset.remove_all(&"b"), 2);
+    assert!(!multiset.remove_one(&"b"));
+    assert_eq!(multiset.remove_all(&"d"), 0);
+
+    let mut avl = AVLMultiset::<i32>::new();
+    avl.insert(1);
+    avl.insert(1);
+    let mut iter = avl.iter();
+    assert_eq!(iter.next(), Some(&1));
+    assert_eq!(iter.next_back(), Some(&1));
+    assert_eq!(iter.next(), None);
+    assert_eq!(iter.next_back(), None);
+}
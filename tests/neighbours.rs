@@ -0,0 +1,62 @@
+mod common;
+
+use baltree::{AVLTree, Backend, RedBlackTree};
+use common::{check_all, Check, Family, Set};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::BTreeSet;
+use std::ops::Bound;
+
+struct Neighbours;
+
+impl Check for Neighbours {
+    fn check<F: Family, B: Backend>(seed: u64) {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let model: BTreeSet<i32> = (0..300).map(|_| rng.gen_range(0, 1_000)).collect();
+        let mut tree = F::Set::<B>::default();
+        for key in &model {
+            tree.insert(*key);
+        }
+        for key in -5..1_005 {
+            let below = |bound| model.range((Bound::Unbounded, bound)).next_back().copied();
+            let above = |bound| model.range((bound, Bound::Unbounded)).next().copied();
+            assert_eq!(tree.floor(&key), below(Bound::Included(key)));
+            assert_eq!(tree.predecessor(&key), below(Bound::Excluded(key)));
+            assert_eq!(tree.ceiling(&key), above(Bound::Included(key)));
+            assert_eq!(tree.successor(&key), above(Bound::Excluded(key)));
+        }
+    }
+}
+
+#[test]
+fn neighbours_agree_with_btree_set_ranges() {
+    check_all::<Neighbours>(5);
+}
+
+#[test]
+fn neighbours_at_the_ends() {
+    let mut tree = AVLTree::<i32>::new();
+    for key in [20, 10, 30] {
+        tree.insert(key);
+    }
+    assert_eq!(tree.floor(&5), None);
+    assert_eq!(tree.floor(&10), Some(10));
+    assert_eq!(tree.predecessor(&10), None);
+    assert_eq!(tree.ceiling(&35), None);
+    assert_eq!(tree.ceiling(&30), Some(30));
+    assert_eq!(tree.successor(&30), None);
+    assert_eq!(tree.successor(&15), Some(20));
+    assert_eq!(tree.predecessor(&25), Some(20));
+    assert_eq!((tree.first(), tree.last()), (Some(10), Some(30)));
+    assert_eq!(tree.pop_first(), Some(10));
+    assert_eq!(tree.pop_last(), Some(30));
+    assert_eq!(tree.pop_last(), Some(20));
+    assert_eq!(tree.pop_first(), None);
+
+    let empty = RedBlackTree::<i32>::new();
+    assert_eq!(empty.floor(&0), None);
+    assert_eq!(empty.ceiling(&0), None);
+    assert_eq!(empty.predecessor(&0), None);
+    assert_eq!(empty.successor(&0), None);
+    assert_eq!((empty.first(), empty.last()), (None, None));
+}
@@ -0,0 +1,89 @@
+use baltree::{AVLTree, ArenaBackend, Backend, NodeColor, NodeRef, RcBackend, RedBlackTree};
+
+// Collects the keys below `node` in order, checking the height of every node on the way
+fn walk<B: Backend>(node: Option<NodeRef<'_, i32, (), B>>, keys: &mut Vec<i32>) -> i32 {
+    let Some(node) = node else {
+        return 0;
+    };
+    let left = walk(node.left(), keys);
+    keys.push(*node.key());
+    let right = walk(node.right(), keys);
+    assert_eq!(node.height(), 1 + left.max(right));
+    node.height()
+}
+
+fn views<B: Backend>() {
+    let avl = AVLTree::<i32, B>::from_iter((0..100).rev());
+    let root = avl.root().unwrap();
+    assert_eq!(root.color(), None);
+    assert!(root.height() <= 8);
+    let mut keys = Vec::new();
+    walk(avl.root(), &mut keys);
+    assert_eq!(keys, (0..100).collect::<Vec<_>>());
+
+    let mut rb = RedBlackTree::<i32, B>::default();
+    for key in 0..100 {
+        rb.insert(key);
+    }
+    assert_eq!(rb.root().unwrap().color(), Some(NodeColor::Black));
+    let mut keys = Vec::new();
+    walk(rb.root(), &mut keys);
+    assert_eq!(keys, (0..100).collect::<Vec<_>>());
+
+    // Deleting, splitting and joining rotate red-black trees in other ways
+    for key in (0..100).step_by(3) {
+        rb.delete(&key);
+    }
+    let mut larger = rb.split_off(&50);
+    let mut keys = Vec::new();
+    walk(rb.root(), &mut keys);
+    walk(larger.root(), &mut keys);
+    assert!(keys.iter().copied().eq((0..100).filter(|key| key % 3 != 0)));
+    rb.append(&mut larger);
+    let joined = RedBlackTree::join(rb, 1_000, RedBlackTree::from_iter(1_001..1_200));
+    let mut keys = Vec::new();
+    walk(joined.root(), &mut keys);
+    assert_eq!(keys.len(), 66 + 200);
+}
+
+#[test]
+fn node_views_show_the_shape_of_the_tree() {
+    views::<RcBackend>();
+    views::<ArenaBackend>();
+    assert!(AVLTree::<i32>::new().root().is_none());
+    assert!(RedBlackTree::<i32>::new().root().is_none());
+}
+
+#[test]
+fn node_views_follow_rebalancing() {
+    let mut tree = AVLTree::<i32>::new();
+    tree.insert(1);
+    tree.insert(2);
+    assert_eq!(*tree.root().unwrap().key(), 1);
+    tree.insert(3);
+    let root = tree.root().unwrap();
+    assert_eq!(*root.key(), 2);
+    assert_eq!(root.height(), 2);
+    assert_eq!(root.left().map(|left| *left.key()), Some(1));
+    assert_eq!(root.right().map(|right| *right.key()), Some(3));
+}
+
+// Keys that cannot be cloned, so only the trees' own methods can be called on them
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+struct Token(u32);
+
+#[test]
+fn set_methods_work_without_clone() {
+    let mut avl = AVLTree::new();
+    let mut rb = RedBlackTree::new();
+    assert!(avl.is_empty() && rb.is_empty());
+    for id in 0..10 {
+        avl.insert(Token(id));
+        rb.insert(Token(id));
+    }
+    assert_eq!((avl.len(), rb.len()), (10, 10));
+    assert!(avl.contains(&Token(3)) && rb.contains(&Token(9)));
+    assert!(!avl.contains(&Token(10)) && !rb.contains(&Token(10)));
+    assert_eq!(avl.height(), avl.root().unwrap().height());
+    assert_eq!(rb.height(), rb.root().unwrap().height());
+}
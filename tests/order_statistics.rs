@@ -0,0 +1,76 @@
+mod common;
+
+use baltree::{AVLTree, Backend, RedBlackTree};
+use common::{check_all, Check, Family, Set};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::BTreeSet;
+
+// Mixes inserts with removals by key and by position
+struct Statistics;
+
+impl Check for Statistics {
+    fn check<F: Family, B: Backend>(seed: u64) {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut tree = F::Set::<B>::default();
+        let mut model = BTreeSet::new();
+        for step in 0..2_000 {
+            let key = rng.gen_range(0, 500);
+            if rng.gen_bool(0.6) {
+                tree.insert(key);
+                model.insert(key);
+            } else if rng.gen_bool(0.5) {
+                tree.delete(&key);
+                model.remove(&key);
+            } else {
+                // Removing by position has to pick the same key as the model
+                let index = rng.gen_range(0, model.len() + 2);
+                let expected = model.iter().nth(index).copied();
+                assert_eq!(tree.remove_at(index), expected);
+                if let Some(key) = expected {
+                    model.remove(&key);
+                }
+            }
+            assert_eq!(tree.len(), model.len());
+            if step % 50 != 0 {
+                continue;
+            }
+            assert_eq!(tree.validate(), Ok(()));
+            let keys: Vec<i32> = model.iter().copied().collect();
+            for (index, key) in keys.iter().enumerate() {
+                assert_eq!(tree.select(index), Some(*key));
+                assert_eq!(tree.rank(key), index);
+            }
+            assert_eq!(tree.select(keys.len()), None);
+            // Missing keys rank among the keys around them
+            for key in -1..=501 {
+                assert_eq!(tree.rank(&key), keys.partition_point(|other| *other < key));
+            }
+        }
+    }
+}
+
+#[test]
+fn select_rank_and_remove_at_agree_with_a_sorted_set() {
+    check_all::<Statistics>(3);
+}
+
+#[test]
+fn order_statistics_on_small_trees() {
+    let mut empty = AVLTree::<i32>::new();
+    assert_eq!(empty.select(0), None);
+    assert_eq!(empty.rank(&5), 0);
+    assert_eq!(empty.remove_at(0), None);
+
+    let mut tree = RedBlackTree::<i32>::new();
+    for key in [30, 10, 20] {
+        tree.insert(key);
+    }
+    assert_eq!(tree.select(1), Some(20));
+    assert_eq!(tree.rank(&25), 2);
+    assert_eq!(tree.remove_at(2), Some(30));
+    assert_eq!(tree.remove_at(2), None);
+    assert_eq!(tree.remove_at(0), Some(10));
+    assert_eq!(tree.remove_at(0), Some(20));
+    assert!(tree.is_empty());
+}
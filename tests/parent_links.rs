@@ -0,0 +1,106 @@
+use baltree::{AVLTree, ArenaBackend, Backend, Cursor, RcBackend, RedBlackTree};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use std::collections::BTreeSet;
+use std::ops::Bound;
+
+// A cursor placed on each key by a search has to step to the neighbouring keys, which only
+// works when the parent links are right
+fn assert_neighbours<'a, B: Backend + 'a>(
+    lower_bound: impl Fn(&i32) -> Cursor<'a, i32, (), B>,
+    keys: &BTreeSet<i32>,
+) {
+    let keys: Vec<i32> = keys.iter().copied().collect();
+    for (index, key) in keys.iter().enumerate() {
+        let cursor = lower_bound(key);
+        assert_eq!(cursor.key(), Some(key));
+        assert_eq!(cursor.peek_next(), keys.get(index + 1));
+        assert_eq!(
+            cursor.peek_prev(),
+            index.checked_sub(1).map(|prev| &keys[prev])
+        );
+    }
+}
+
+fn shuffled(count: i32, seed: u64) -> Vec<i32> {
+    let mut keys: Vec<i32> = (0..count).collect();
+    keys.shuffle(&mut StdRng::seed_from_u64(seed));
+    keys
+}
+
+fn walk_avl<B: Backend>(seed: u64) {
+    let mut tree = AVLTree::<i32, B>::default();
+    let mut keys = BTreeSet::new();
+    for key in shuffled(200, seed) {
+        tree.insert(key);
+        keys.insert(key);
+    }
+    assert_neighbours(|key| tree.lower_bound(Bound::Included(key)), &keys);
+    for key in shuffled(200, seed + 1).into_iter().take(120) {
+        tree.delete(&key);
+        keys.remove(&key);
+        assert_eq!(tree.validate(), Ok(()));
+    }
+    assert_neighbours(|key| tree.lower_bound(Bound::Included(key)), &keys);
+
+    let other = tree.split_off(&100);
+    let (smaller, larger): (BTreeSet<i32>, BTreeSet<i32>) =
+        keys.iter().partition(|&&key| key < 100);
+    assert_neighbours(|key| tree.lower_bound(Bound::Included(key)), &smaller);
+    assert_neighbours(|key| other.lower_bound(Bound::Included(key)), &larger);
+    let joined = AVLTree::join(tree, 1_000, AVLTree::from_iter(1_001..1_050));
+    assert_eq!(joined.validate(), Ok(()));
+    let union = joined.into_union(other);
+    assert_eq!(union.validate(), Ok(()));
+}
+
+fn walk_red_black<B: Backend>(seed: u64) {
+    let mut tree = RedBlackTree::<i32, B>::default();
+    let mut keys = BTreeSet::new();
+    for key in shuffled(200, seed) {
+        tree.insert(key);
+        keys.insert(key);
+    }
+    for key in shuffled(200, seed + 1).into_iter().take(120) {
+        tree.delete(&key);
+        keys.remove(&key);
+    }
+    assert_neighbours(|key| tree.lower_bound(Bound::Included(key)), &keys);
+}
+
+#[test]
+fn avl_nodes_lead_to_their_neighbours() {
+    for seed in 0..10 {
+        walk_avl::<RcBackend>(seed);
+        walk_avl::<ArenaBackend>(seed);
+    }
+}
+
+#[test]
+fn red_black_nodes_lead_to_their_neighbours() {
+    for seed in 0..10 {
+        walk_red_black::<RcBackend>(seed);
+        walk_red_black::<ArenaBackend>(seed);
+    }
+}
+
+#[test]
+fn walking_covers_the_whole_tree() {
+    let tree = AVLTree::<i32>::from_iter(0..1_000);
+    let mut cursor = tree.cursor_front();
+    let mut count = 0;
+    while cursor.key().is_some() {
+        count += 1;
+        cursor.move_next();
+    }
+    assert_eq!(count, 1_000);
+
+    let mut cursor = tree.cursor_back();
+    let mut count = 0;
+    while cursor.key().is_some() {
+        count += 1;
+        cursor.move_prev();
+    }
+    assert_eq!(count, 1_000);
+}
@@ -0,0 +1,111 @@
+mod common;
+
+use baltree::Backend;
+use common::{check_kinds, Check, Family, Persistent};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::BTreeSet;
+
+// Keeps every version with the set it should hold, and checks all of them at the end
+struct Versions;
+
+impl Check for Versions {
+    fn check<F: Family, B: Backend>(seed: u64) {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut versions = vec![(F::Persistent::default(), BTreeSet::new())];
+        for _ in 0..600 {
+            // Changes are made to any older version, not only the latest one
+            let (tree, model) = &versions[rng.gen_range(0, versions.len())];
+            let (mut tree, mut model) = (tree.clone(), model.clone());
+            let key = rng.gen_range(0, 200);
+            if rng.gen_bool(0.6) {
+                tree = tree.insert(key);
+                model.insert(key);
+            } else {
+                tree = tree.remove(&key);
+                model.remove(&key);
+            }
+            versions.push((tree, model));
+        }
+        for (tree, model) in &versions {
+            assert!(tree.iter().eq(model.iter()));
+            assert_eq!(tree.len(), model.len());
+            assert_eq!(tree.is_empty(), model.is_empty());
+            assert_eq!(tree.first(), model.first());
+            assert_eq!(tree.last(), model.last());
+        }
+    }
+}
+
+// Keys in shared nodes sit at the same address in both versions, so counting the keys that
+// moved counts the nodes that were copied
+struct Sharing;
+
+impl Check for Sharing {
+    fn check<F: Family, B: Backend>(_: u64) {
+        let old = (0..1_000).fold(F::Persistent::default(), |tree, key| tree.insert(key));
+        let copied = |new: &F::Persistent| {
+            let old: Vec<*const i32> = old.iter().map(|key| key as *const i32).collect();
+            new.iter()
+                .filter(|key| !old.contains(&(*key as *const i32)))
+                .count()
+        };
+        let limit = 3 * old.height() as usize;
+
+        let inserted = old.insert(1_000);
+        assert!(copied(&inserted) <= limit);
+        assert_eq!(old.len(), 1_000);
+        assert!(!old.contains(&1_000));
+
+        let removed = old.remove(&500);
+        assert!(copied(&removed) <= limit);
+        assert!(old.contains(&500));
+        assert!(!removed.contains(&500));
+
+        // Nothing changes, so nothing is copied
+        assert_eq!(copied(&old.insert(10)), 0);
+        assert_eq!(copied(&old.remove(&5_000)), 0);
+    }
+}
+
+struct Bounded;
+
+impl Check for Bounded {
+    fn check<F: Family, B: Backend>(seed: u64) {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut tree = F::Persistent::default();
+        let mut model = BTreeSet::new();
+        for step in 0..5_000 {
+            let key = rng.gen_range(0, 2_000);
+            if step < 2_500 || rng.gen_bool(0.3) {
+                tree = tree.insert(key);
+                model.insert(key);
+            } else {
+                tree = tree.remove(&key);
+                model.remove(&key);
+            }
+            assert!(
+                f64::from(tree.height()) <= F::max_height(tree.len()),
+                "height {} with {} keys",
+                tree.height(),
+                tree.len()
+            );
+        }
+        assert!(tree.iter().eq(model.iter()));
+    }
+}
+
+#[test]
+fn old_versions_are_unchanged() {
+    check_kinds::<Versions>(5);
+}
+
+#[test]
+fn versions_share_untouched_subtrees() {
+    check_kinds::<Sharing>(1);
+}
+
+#[test]
+fn heights_stay_within_their_bounds() {
+    check_kinds::<Bounded>(5);
+}
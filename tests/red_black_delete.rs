@@ -0,0 +1,108 @@
+use baltree::{ArenaBackend, Backend, IntervalTree, RedBlackMap, RedBlackMultiset, RedBlackTree};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use std::collections::BTreeSet;
+
+fn shuffled(keys: &[i32], seed: u64) -> Vec<i32> {
+    let mut keys = keys.to_vec();
+    keys.shuffle(&mut StdRng::seed_from_u64(seed));
+    keys
+}
+
+// Builds a tree from the keys in one random order and deletes them in another, checking
+// every property of the tree after each deletion
+fn delete_all<B: Backend>(count: i32, seed: u64) {
+    let keys: Vec<i32> = (0..count).collect();
+    let mut tree = RedBlackTree::<i32, B>::default();
+    for key in shuffled(&keys, seed) {
+        tree.insert(key);
+    }
+    let mut left = tree.len();
+    for key in shuffled(&keys, seed + 1) {
+        assert_eq!(tree.delete(&key), Some(key));
+        assert_eq!(tree.delete(&key), None);
+        left -= 1;
+        assert_eq!(tree.len(), left);
+        assert!(!tree.contains(&key));
+        assert_eq!(tree.validate(), Ok(()));
+    }
+    assert!(tree.is_empty());
+    assert!(tree.root().is_none());
+}
+
+#[test]
+fn deleting_every_key_keeps_the_tree_valid() {
+    for count in [1, 2, 3, 10, 100] {
+        for seed in 0..20 {
+            delete_all::<baltree::RcBackend>(count, seed);
+            delete_all::<ArenaBackend>(count, seed);
+        }
+    }
+    delete_all::<ArenaBackend>(3_000, 99);
+}
+
+#[test]
+fn deleting_in_order_keeps_the_tree_valid() {
+    let mut tree = RedBlackTree::<i32>::from_iter(0..500);
+    for key in 0..250 {
+        assert_eq!(tree.delete(&key), Some(key));
+        assert_eq!(tree.delete(&(499 - key)), Some(499 - key));
+        assert_eq!(tree.validate(), Ok(()));
+    }
+    assert!(tree.is_empty());
+}
+
+#[test]
+fn trees_stay_usable_after_being_emptied() {
+    let mut tree = RedBlackTree::<i32>::new();
+    for round in 0..5 {
+        for key in shuffled(&(0..50).collect::<Vec<_>>(), round) {
+            tree.insert(key);
+        }
+        for key in shuffled(&(0..50).collect::<Vec<_>>(), round + 10) {
+            tree.delete(&key);
+        }
+        assert!(tree.is_empty());
+        assert_eq!(tree.validate(), Ok(()));
+    }
+}
+
+#[test]
+fn maps_multisets_and_interval_trees_delete_every_key() {
+    let keys: Vec<i32> = (0..300).collect();
+
+    let mut map = RedBlackMap::<i32, String>::new();
+    for key in shuffled(&keys, 1) {
+        map.insert(key, key.to_string());
+    }
+    for key in shuffled(&keys, 2) {
+        assert_eq!(map.remove(&key), Some(key.to_string()));
+    }
+    assert!(map.is_empty());
+
+    let mut multiset = RedBlackMultiset::<i32>::new();
+    for key in shuffled(&keys, 3) {
+        multiset.insert(key % 100);
+    }
+    for key in shuffled(&keys, 4) {
+        assert!(multiset.remove_one(&(key % 100)));
+    }
+    assert!(multiset.is_empty());
+
+    let mut intervals = IntervalTree::<i32>::new();
+    for key in shuffled(&keys, 5) {
+        assert_eq!(intervals.insert((key, key + 10)), Ok(true));
+    }
+    let mut starts: BTreeSet<i32> = keys.iter().copied().collect();
+    for key in shuffled(&keys, 6) {
+        assert_eq!(intervals.remove(&(key, key + 10)), Some((key, key + 10)));
+        starts.remove(&key);
+        // The largest ends kept below each node have to follow the deletions
+        assert_eq!(
+            intervals.stab(key).count(),
+            starts.range(key - 9..=key).count()
+        );
+    }
+    assert!(intervals.is_empty());
+}
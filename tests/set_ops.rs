@@ -0,0 +1,115 @@
+mod common;
+
+use baltree::{AVLTree, ArenaBackend, Backend, RcBackend, RedBlackTree};
+use common::build;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::BTreeSet;
+
+// Pairs of operands that overlap fully, partly or not at all, are empty, or differ a lot in size
+fn operands() -> Vec<(BTreeSet<i32>, BTreeSet<i32>)> {
+    let mut rng = StdRng::seed_from_u64(11);
+    let mut random = |count: usize, keys: i32| -> BTreeSet<i32> {
+        (0..count).map(|_| rng.gen_range(0, keys)).collect()
+    };
+    vec![
+        (BTreeSet::new(), BTreeSet::new()),
+        ((0..50).collect(), BTreeSet::new()),
+        (BTreeSet::new(), (0..50).collect()),
+        ((0..100).collect(), (0..100).collect()),
+        ((0..100).collect(), (100..200).collect()),
+        ((0..100).step_by(2).collect(), (0..100).step_by(3).collect()),
+        (random(500, 1_000), random(500, 1_000)),
+        (random(2_000, 5_000), random(10, 5_000)),
+        (random(3, 100), random(1_000, 100)),
+    ]
+}
+
+fn expected(left: &BTreeSet<i32>, right: &BTreeSet<i32>) -> [Vec<i32>; 4] {
+    [
+        left.union(right).copied().collect(),
+        left.intersection(right).copied().collect(),
+        left.difference(right).copied().collect(),
+        left.symmetric_difference(right).copied().collect(),
+    ]
+}
+
+fn lazy<B: Backend>() {
+    for (left, right) in operands() {
+        let [union, intersection, difference, symmetric] = expected(&left, &right);
+        let avl = build::<AVLTree<i32, B>>(left.iter().copied());
+        let rb = build::<RedBlackTree<i32, B>>(right.iter().copied());
+        assert_eq!(avl.union(&rb).copied().collect::<Vec<_>>(), union);
+        assert_eq!(
+            avl.intersection(&rb).copied().collect::<Vec<_>>(),
+            intersection
+        );
+        assert_eq!(avl.difference(&rb).copied().collect::<Vec<_>>(), difference);
+        assert_eq!(
+            avl.symmetric_difference(&rb).copied().collect::<Vec<_>>(),
+            symmetric
+        );
+
+        let other = build::<AVLTree<i32, B>>(right.iter().copied());
+        assert_eq!(avl.union(&other).copied().collect::<Vec<_>>(), union);
+        let rb = build::<RedBlackTree<i32, B>>(left.iter().copied());
+        assert_eq!(
+            rb.difference(&other).copied().collect::<Vec<_>>(),
+            difference
+        );
+    }
+}
+
+fn building<B: Backend>() {
+    for (left, right) in operands() {
+        let [union, intersection, difference, symmetric] = expected(&left, &right);
+        let avl = || build::<AVLTree<i32, B>>(left.iter().copied());
+        let rb = || build::<RedBlackTree<i32, B>>(left.iter().copied());
+        let other_avl = || build::<AVLTree<i32, B>>(right.iter().copied());
+        let other_rb = || build::<RedBlackTree<i32, B>>(right.iter().copied());
+
+        let results = [
+            avl().into_union(other_avl()),
+            avl().into_intersection(other_rb()),
+            avl().into_difference(other_avl()),
+            avl().into_symmetric_difference(other_rb()),
+        ];
+        for (result, expected) in
+            results
+                .iter()
+                .zip([&union, &intersection, &difference, &symmetric])
+        {
+            assert_eq!(result.validate(), Ok(()));
+            assert_eq!(result.iter().copied().collect::<Vec<_>>(), *expected);
+            assert_eq!(result.len(), expected.len());
+        }
+
+        let results = [
+            rb().into_union(other_avl()),
+            rb().into_intersection(other_rb()),
+            rb().into_difference(other_avl()),
+            rb().into_symmetric_difference(other_rb()),
+        ];
+        for (result, expected) in
+            results
+                .iter()
+                .zip([&union, &intersection, &difference, &symmetric])
+        {
+            assert_eq!(result.validate(), Ok(()));
+            assert_eq!(result.iter().copied().collect::<Vec<_>>(), *expected);
+            assert_eq!(result.len(), expected.len());
+        }
+    }
+}
+
+#[test]
+fn lazy_set_operations_agree_with_btree_set() {
+    lazy::<RcBackend>();
+    lazy::<ArenaBackend>();
+}
+
+#[test]
+fn built_set_operations_agree_with_btree_set() {
+    building::<RcBackend>();
+    building::<ArenaBackend>();
+}
@@ -0,0 +1,64 @@
+mod common;
+
+use baltree::{AVLTree, ArenaBackend, Backend, RedBlackTree, TreeError};
+use common::{check_all, Check, Family, Set};
+
+struct SortedBuild;
+
+impl Check for SortedBuild {
+    fn check<F: Family, B: Backend>(_: u64) {
+        for count in [0, 1, 2, 3, 7, 8, 100, 1_023, 1_024, 1_025] {
+            let tree = F::Set::<B>::from_sorted_iter(0..count).unwrap();
+            assert!(tree.keys().into_iter().eq(0..count));
+            assert_eq!(tree.len(), count as usize);
+            // Perfectly balanced: no taller than a complete tree of the same size
+            assert!(tree.height() <= 32 - count.leading_zeros() as i32);
+        }
+    }
+}
+
+#[test]
+fn sorted_keys_build_balanced_trees() {
+    check_all::<SortedBuild>(1);
+}
+
+#[test]
+fn unsorted_keys_are_rejected() {
+    assert_eq!(
+        AVLTree::<i32>::from_sorted_iter([1, 2, 5, 4]).err(),
+        Some(TreeError::NotAscending { index: 3 })
+    );
+    // Duplicates are not strictly ascending either
+    assert_eq!(
+        RedBlackTree::<i32, ArenaBackend>::from_sorted_iter([1, 1]).err(),
+        Some(TreeError::NotAscending { index: 1 })
+    );
+    assert_eq!(
+        RedBlackTree::<i32>::from_sorted_iter([3, 2, 1]).err(),
+        Some(TreeError::NotAscending { index: 1 })
+    );
+    assert_eq!(
+        TreeError::NotAscending { index: 3 }.to_string(),
+        "key at index 3 is not larger than the key before it"
+    );
+}
+
+#[test]
+fn built_trees_take_further_changes() {
+    let mut tree = AVLTree::<i32>::from_sorted_iter((0..100).map(|key| key * 2)).unwrap();
+    for key in 0..100 {
+        tree.insert(key * 2 + 1);
+    }
+    for key in (0..200).step_by(3) {
+        tree.delete(&key);
+    }
+    assert_eq!(tree.validate(), Ok(()));
+    assert!(tree
+        .keys()
+        .into_iter()
+        .eq((0..200).filter(|key| key % 3 != 0)));
+
+    let tree: RedBlackTree<i32> = (0..100).rev().collect();
+    assert_eq!(tree.validate(), Ok(()));
+    assert!(tree.keys().into_iter().eq(0..100));
+}
@@ -0,0 +1,133 @@
+mod common;
+
+use baltree::{AVLTree, Backend, RedBlackTree};
+use common::{build, check_all, Check, Family, Set};
+use std::collections::BTreeSet;
+
+#[test]
+fn appending_overlapping_trees_keeps_keys_unique() {
+    let mut avl: AVLTree<i32> = build(0..5);
+    avl.append(&mut build(4..10));
+    assert_eq!(avl.validate(), Ok(()));
+    assert_eq!(avl.keys(), (0..10).collect::<Vec<_>>());
+
+    let mut rb: RedBlackTree<i32> = build(4..10);
+    rb.append(&mut build(0..5));
+    assert_eq!(rb.validate(), Ok(()));
+    assert_eq!(rb.keys(), (0..10).collect::<Vec<_>>());
+}
+
+#[test]
+#[should_panic(expected = "ordered around the pivot")]
+fn joining_around_a_key_of_the_left_tree_panics() {
+    AVLTree::<i32>::join(build(0..5), 4, build(5..10));
+}
+
+#[test]
+#[should_panic(expected = "ordered around the pivot")]
+fn joining_around_a_key_of_the_right_tree_panics() {
+    RedBlackTree::<i32>::join(build(0..5), 5, build(5..10));
+}
+
+// Key sets for the operands: empty, a single key, and runs that are dense, sparse, far apart
+// or much larger than each other, so the joins meet the taller tree at different depths
+fn operands() -> Vec<BTreeSet<i32>> {
+    vec![
+        BTreeSet::new(),
+        BTreeSet::from([50]),
+        (0..100).collect(),
+        (0..300).step_by(3).collect(),
+        (40..60).collect(),
+        (1_000..1_010).collect(),
+        (-5_000..0).collect(),
+    ]
+}
+
+struct SplitOff;
+
+impl Check for SplitOff {
+    fn check<F: Family, B: Backend>(_: u64) {
+        for keys in operands() {
+            for at in [-10_000, 0, 45, 50, 51, 99, 1_005, 10_000] {
+                let mut tree: F::Set<B> = build(keys.iter().copied());
+                let mut expected = keys.clone();
+                let other = tree.split_off(&at);
+                let expected_other = expected.split_off(&at);
+                assert_eq!(tree.validate(), Ok(()));
+                assert_eq!(other.validate(), Ok(()));
+                assert!(tree.keys().iter().eq(expected.iter()));
+                assert!(other.keys().iter().eq(expected_other.iter()));
+                assert_eq!(tree.len(), expected.len());
+                assert_eq!(other.len(), expected_other.len());
+            }
+        }
+    }
+}
+
+struct Append;
+
+impl Check for Append {
+    fn check<F: Family, B: Backend>(_: u64) {
+        for left in operands() {
+            for right in operands() {
+                let mut tree: F::Set<B> = build(left.iter().copied());
+                let mut other: F::Set<B> = build(right.iter().copied());
+                tree.append(&mut other);
+                assert_eq!(tree.validate(), Ok(()));
+                assert_eq!(other.len(), 0);
+                let expected: Vec<i32> = left.union(&right).copied().collect();
+                assert_eq!(tree.keys(), expected);
+                assert_eq!(tree.len(), expected.len());
+            }
+        }
+    }
+}
+
+struct Join;
+
+impl Check for Join {
+    fn check<F: Family, B: Backend>(_: u64) {
+        let sizes = [0, 1, 2, 7, 100, 1_000];
+        for &smaller in &sizes {
+            for &larger in &sizes {
+                let left: F::Set<B> = build(0..smaller);
+                let right: F::Set<B> = build(smaller + 1..smaller + 1 + larger);
+                let joined = F::Set::<B>::join(left, smaller, right);
+                assert_eq!(joined.validate(), Ok(()));
+                assert!(joined.keys().into_iter().eq(0..smaller + 1 + larger));
+                assert_eq!(joined.len(), (smaller + 1 + larger) as usize);
+            }
+        }
+    }
+}
+
+#[test]
+fn split_off_keeps_both_halves_balanced() {
+    check_all::<SplitOff>(1);
+}
+
+#[test]
+fn append_merges_overlapping_disjoint_and_empty_trees() {
+    check_all::<Append>(1);
+}
+
+#[test]
+fn join_links_trees_of_any_heights() {
+    check_all::<Join>(1);
+}
+
+#[test]
+fn splitting_and_appending_back_restores_the_tree() {
+    let mut avl: AVLTree<i32> = build(0..500);
+    let mut rb: RedBlackTree<i32> = build(0..500);
+    for at in (0..500).step_by(37) {
+        let mut larger = avl.split_off(&at);
+        avl.append(&mut larger);
+        let mut larger = rb.split_off(&at);
+        rb.append(&mut larger);
+        assert_eq!(avl.validate(), Ok(()));
+        assert_eq!(rb.validate(), Ok(()));
+    }
+    assert!(avl.keys().into_iter().eq(0..500));
+    assert!(rb.keys().into_iter().eq(0..500));
+}
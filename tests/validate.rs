@@ -0,0 +1,37 @@
+use baltree::{AVLTree, ArenaBackend, RedBlackTree};
+
+// Pseudo-random keys, the same on every run
+fn keys(count: usize) -> Vec<i32> {
+    let mut seed = 0x2545_f491_u64;
+    (0..count)
+        .map(|_| {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            (seed % 10_000) as i32
+        })
+        .collect()
+}
+
+#[test]
+fn trees_built_by_the_library_are_valid() {
+    let mut avl = AVLTree::<i32, ArenaBackend>::default();
+    let mut rb = RedBlackTree::<i32>::new();
+    for key in keys(2_000) {
+        avl.insert(key);
+        rb.insert(key);
+        assert_eq!(avl.validate(), Ok(()));
+    }
+    assert_eq!(rb.validate(), Ok(()));
+    for key in keys(1_000) {
+        avl.delete(&key);
+    }
+    assert_eq!(avl.validate(), Ok(()));
+
+    let split = rb.split_off(&5_000);
+    assert_eq!(rb.validate(), Ok(()));
+    assert_eq!(split.validate(), Ok(()));
+    assert_eq!(RedBlackTree::<i32>::from_iter(keys(300)).validate(), Ok(()));
+    assert_eq!(AVLTree::<i32>::from_iter(keys(300)).validate(), Ok(()));
+    assert_eq!(RedBlackTree::<i32>::new().validate(), Ok(()));
+}